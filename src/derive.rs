@@ -0,0 +1,393 @@
+//! Derivation of typed routes for an enum.
+//!
+//! `#[derive(Routes)]` is applied to an enum where each variant has an `#[at("/path")]` attribute,
+//! with a field for each parameter in the path. It generates:
+//!
+//! - a `Display` implementation, which builds the URL of a route,
+//! - a `FromStr` implementation, which matches a URL against each of the routes in turn, and
+//! - a `router` function, which returns a `poem::Route` with a handler for each of the routes that
+//!   has methods, such as `#[at("/pastes/:id", GET POST)]`.
+//!
+//! The `Display` and `FromStr` implementations only use the standard library, so that the enum can
+//! be shared with a frontend that is compiled to WASM. The `router` function is not generated when
+//! compiling to WASM, or when none of the variants have methods, so that an enum without methods
+//! does not depend on Poem at all.
+
+use quote::{format_ident, quote, quote_spanned};
+use syn::{
+    parse::{Parse, ParseStream},
+    Token,
+};
+
+use crate::{
+    check_routes,
+    path::{RoutePath, Segment},
+    render_routes,
+    urls::render_encode,
+    Handlers, MethodEntry, Options, Route, StandardRoute,
+};
+
+/// The arguments of the `at` attribute, such as `"/pastes/:id", GET POST`.
+struct AtArgs {
+    path: RoutePath,
+    methods: Vec<MethodEntry>,
+}
+
+impl Parse for AtArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let path = input.parse()?;
+        let mut methods = Vec::new();
+        if input.parse::<Option<Token![,]>>()?.is_some() {
+            while !input.is_empty() {
+                methods.push(input.parse()?);
+            }
+        }
+
+        Ok(Self { path, methods })
+    }
+}
+
+/// Convert the name of a variant into the snake case name of its handler, such as `paste_raw` for
+/// `PasteRaw`.
+fn snake_case(ident: &syn::Ident) -> syn::Ident {
+    let name = ident.to_string();
+    let chars = name.chars().collect::<Vec<_>>();
+    let mut snake = String::new();
+
+    for (index, &c) in chars.iter().enumerate() {
+        if c.is_ascii_uppercase() && index > 0 {
+            let prev = chars[index - 1];
+            let next = chars.get(index + 1);
+            if prev.is_ascii_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_ascii_uppercase() && next.is_some_and(|c| c.is_ascii_lowercase()))
+            {
+                snake.push('_');
+            }
+        }
+
+        snake.push(c.to_ascii_lowercase());
+    }
+
+    syn::Ident::new(&snake, ident.span())
+}
+
+/// The code generated for a single variant.
+struct RenderedVariant {
+    /// The arm of the `match` in the `Display` implementation.
+    display: proc_macro2::TokenStream,
+    /// The statement that tries to match the variant in the `FromStr` implementation.
+    parse: proc_macro2::TokenStream,
+    /// The route for the `router` function, if the variant has any methods.
+    route: Option<Route>,
+}
+
+/// Bind each parameter of the path to a field of the variant, returning the pattern that
+/// destructures the variant and the name bound to each of the parameters.
+fn bind_fields(
+    variant: &syn::Variant,
+    path: &RoutePath,
+) -> syn::Result<(proc_macro2::TokenStream, Vec<syn::Ident>)> {
+    let ident = &variant.ident;
+    let params = path
+        .segments
+        .iter()
+        .filter(|segment| !matches!(segment, Segment::Static(_)))
+        .collect::<Vec<_>>();
+
+    for segment in &params {
+        let message = match segment {
+            Segment::Regex(_, _) => "regular expressions are not supported by `derive(Routes)`",
+            Segment::CatchAll(None) => "a wildcard must be named, such as `*path`",
+            _ => continue,
+        };
+
+        return Err(syn::Error::new(
+            path.span(),
+            format!("invalid route path: {message}"),
+        ));
+    }
+
+    match &variant.fields {
+        syn::Fields::Unit if params.is_empty() => Ok((quote! { Self::#ident }, Vec::new())),
+
+        syn::Fields::Named(fields) => {
+            let mut bound = Vec::new();
+            for segment in &params {
+                let name = segment.name().unwrap_or_default();
+                let field = fields
+                    .named
+                    .iter()
+                    .filter_map(|field| field.ident.as_ref())
+                    .find(|field| *field == name);
+
+                match field {
+                    Some(field) => bound.push(field.clone()),
+                    None => {
+                        return Err(syn::Error::new(
+                            path.span(),
+                            format!("no field `{name}` for the parameter `{segment}`"),
+                        ))
+                    }
+                }
+            }
+
+            if let Some(field) = fields
+                .named
+                .iter()
+                .filter_map(|field| field.ident.as_ref())
+                .find(|field| !bound.contains(field))
+            {
+                return Err(syn::Error::new(
+                    field.span(),
+                    format!("the field `{field}` is not a parameter of the route"),
+                ));
+            }
+
+            Ok((quote! { Self::#ident { #(#bound),* } }, bound))
+        }
+
+        syn::Fields::Unnamed(fields) if fields.unnamed.len() == params.len() => {
+            let bound = (0..params.len())
+                .map(|index| format_ident!("field{}", index))
+                .collect::<Vec<_>>();
+            Ok((quote! { Self::#ident(#(#bound),*) }, bound))
+        }
+
+        _ => Err(syn::Error::new(
+            variant.ident.span(),
+            format!(
+                "the variant `{ident}` must have a field for each parameter of the route `{}`",
+                path.lit.value()
+            ),
+        )),
+    }
+}
+
+/// Render the `Display` arm, the `FromStr` matching and the route for a variant.
+fn render_variant(variant: &syn::Variant) -> syn::Result<RenderedVariant> {
+    let ident = &variant.ident;
+    let attr = variant
+        .attrs
+        .iter()
+        .find(|attr| attr.path().is_ident("at"))
+        .ok_or_else(|| {
+            syn::Error::new(
+                ident.span(),
+                format!("the variant `{ident}` must have an `#[at(\"...\")]` attribute"),
+            )
+        })?;
+
+    let AtArgs { path, methods } = attr.parse_args()?;
+    let (pattern, bound) = bind_fields(variant, &path)?;
+
+    // The locals are given mixed-site hygiene, so that they are not confused with the fields of the
+    // variant, which are named after the parameters of the route.
+    let span = proc_macro2::Span::mixed_site();
+    let mut display = Vec::new();
+    let mut parse = Vec::new();
+    let mut fields = bound.iter();
+
+    for segment in &path.segments {
+        match segment {
+            Segment::Static(text) => {
+                display.push(quote_spanned! {span=>
+                    f.write_str(#text)?;
+                });
+
+                parse.push(quote_spanned! {span=>
+                    let rest = rest.strip_prefix(#text)?;
+                });
+            }
+
+            Segment::CatchAll(_) => {
+                let field = fields.next();
+                display.push(quote_spanned! {span=>
+                    encoding::encode(#field.to_string().trim_start_matches('/'), true, f)?;
+                });
+
+                parse.push(quote_spanned! {span=>
+                    let #field = encoding::decode(rest)?.parse().ok()?;
+                    let rest = "";
+                });
+            }
+
+            _ => {
+                let field = fields.next();
+                display.push(quote_spanned! {span=>
+                    encoding::encode(&#field.to_string(), false, f)?;
+                });
+
+                parse.push(quote_spanned! {span=>
+                    let (value, rest) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+                    if value.is_empty() {
+                        return None;
+                    }
+
+                    let #field = encoding::decode(value)?.parse().ok()?;
+                });
+            }
+        }
+    }
+
+    let display = quote_spanned! {span=>
+        #[allow(unused_variables)]
+        #pattern => {
+            #(#display)*
+            Ok(())
+        }
+    };
+
+    let parse = quote_spanned! {span=>
+        let matched = (|| {
+            let rest = path;
+            #(#parse)*
+            if !rest.is_empty() {
+                return None;
+            }
+
+            Some(#pattern)
+        })();
+
+        if let Some(route) = matched {
+            return Ok(route);
+        }
+    };
+
+    let route = (!methods.is_empty()).then(|| {
+        Route::Standard(StandardRoute {
+            path,
+            handlers: Handlers::Template {
+                ident: snake_case(ident).into(),
+                methods,
+            },
+            modifiers: Default::default(),
+        })
+    });
+
+    Ok(RenderedVariant {
+        display,
+        parse,
+        route,
+    })
+}
+
+/// Derive the `Display` and `FromStr` implementations, and the `router` function, for an enum.
+pub(crate) fn derive_routes(input: syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let syn::Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new(
+            input.ident.span(),
+            "`Routes` can only be derived for enums",
+        ));
+    };
+
+    let mut error: Option<syn::Error> = None;
+    let mut variants = Vec::new();
+    for variant in &data.variants {
+        match render_variant(variant) {
+            Ok(variant) => variants.push(variant),
+            Err(err) => match &mut error {
+                Some(error) => error.combine(err),
+                None => error = Some(err),
+            },
+        }
+    }
+
+    if let Some(error) = error {
+        return Err(error);
+    }
+
+    let routes = variants
+        .iter_mut()
+        .filter_map(|variant| variant.route.take())
+        .collect::<Vec<_>>();
+    check_routes(&routes)?;
+
+    let ident = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+
+    let router = if routes.is_empty() {
+        proc_macro2::TokenStream::new()
+    } else {
+        let rendered = render_routes(&routes, &Options::default());
+        quote! {
+            #[cfg(not(target_arch = "wasm32"))]
+            impl #impl_generics #ident #type_generics #where_clause {
+                /// Build the routes, with a handler for each of the methods of each route.
+                pub fn router() -> poem::Route {
+                    poem::Route::new() #(#rendered)*
+                }
+            }
+        }
+    };
+
+    let display = variants.iter().map(|variant| &variant.display);
+    let parse = variants.iter().map(|variant| &variant.parse);
+    let span = proc_macro2::Span::mixed_site();
+
+    let encode = render_encode(quote! { pub(super) });
+
+    Ok(quote_spanned! {span=>
+        const _: () = {
+            mod encoding {
+                #encode
+
+                /// Decode a percent-encoded value from the URL.
+                pub(super) fn decode(value: &str) -> ::std::option::Option<::std::string::String> {
+                    let mut bytes = ::std::vec::Vec::with_capacity(value.len());
+                    let mut rest = value.as_bytes();
+                    while let Some((&byte, after)) = rest.split_first() {
+                        if byte == b'%' {
+                            let hex = ::std::str::from_utf8(after.get(..2)?).ok()?;
+                            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+                            rest = &after[2..];
+                        } else {
+                            bytes.push(byte);
+                            rest = after;
+                        }
+                    }
+
+                    ::std::string::String::from_utf8(bytes).ok()
+                }
+            }
+
+            impl #impl_generics ::std::fmt::Display for #ident #type_generics #where_clause {
+                fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                    match self {
+                        #(#display)*
+                    }
+                }
+            }
+
+            impl #impl_generics ::std::str::FromStr for #ident #type_generics #where_clause {
+                type Err = ::std::string::String;
+
+                fn from_str(url: &str) -> ::std::result::Result<Self, Self::Err> {
+                    let path = url.split(['?', '#']).next().unwrap_or_default();
+                    #(#parse)*
+                    Err(::std::format!("no route matches `{url}`"))
+                }
+            }
+
+            #router
+        };
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::snake_case;
+
+    #[test]
+    fn converts_to_snake_case() {
+        let snake = |name: &str| {
+            snake_case(&syn::Ident::new(name, proc_macro2::Span::call_site())).to_string()
+        };
+
+        assert_eq!(snake("Index"), "index");
+        assert_eq!(snake("PasteRaw"), "paste_raw");
+        assert_eq!(snake("HTTPStatus"), "http_status");
+        assert_eq!(snake("ApiV2Users"), "api_v2_users");
+        assert_eq!(snake("Page404"), "page404");
+    }
+}
@@ -0,0 +1,157 @@
+//! Routes declared on the handlers themselves.
+//!
+//! The `#[get("/path")]` attribute, and the others like it, apply Poem's `#[handler]` attribute to
+//! the function and record the path and method of the route as hidden constants on the handler
+//! type. The `collect_routes!` macro then reads these constants to build a `Route`, merging the
+//! handlers that share a path into a single method router.
+//!
+//! The paths of the handlers are not known when `collect_routes!` is expanded, so the handlers are
+//! merged when the routes are built rather than when the macro is expanded.
+
+use quote::quote;
+use syn::{
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    Token,
+};
+
+use crate::{path::RoutePath, Method};
+
+/// The name of the hidden constant that holds the path of a handler.
+const PATH_CONST: &str = "__POEM_ROUTE_PATH";
+/// The name of the hidden constant that holds the method of a handler.
+const METHOD_CONST: &str = "__POEM_ROUTE_METHOD";
+
+/// The arguments of the `route` attribute, such as `"/collections/:id", PROPFIND`.
+struct RouteArgs {
+    path: RoutePath,
+    method: Method,
+}
+
+impl Parse for RouteArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let path = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let method = input.parse()?;
+        input.parse::<Option<Token![,]>>()?;
+
+        if let Method::Any(any) = &method {
+            return Err(syn::Error::new(
+                any.span,
+                "the `ANY` method cannot be used with the `route` attribute",
+            ));
+        }
+
+        Ok(Self { path, method })
+    }
+}
+
+/// Expand an attribute for a specific method, such as `#[get("/pastes/:id")]`, where the method is
+/// given in upper-case.
+pub(crate) fn expand_method_attribute(
+    method: &str,
+    args: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let path = syn::parse::<RoutePath>(args)?;
+    let item = syn::parse::<syn::ItemFn>(item)?;
+    Ok(render_handler(&path, method, item))
+}
+
+/// Expand the `route` attribute, such as `#[route("/collections/:id", PROPFIND)]`.
+pub(crate) fn expand_route_attribute(
+    args: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let RouteArgs { path, method } = syn::parse(args)?;
+    let item = syn::parse::<syn::ItemFn>(item)?;
+    Ok(render_handler(&path, &method.render().to_uppercase(), item))
+}
+
+/// Render a handler function with the given route, applying Poem's `#[handler]` attribute and
+/// recording the path and method of the route.
+fn render_handler(path: &RoutePath, method: &str, item: syn::ItemFn) -> proc_macro2::TokenStream {
+    let ident = &item.sig.ident;
+    let (impl_generics, type_generics, where_clause) = item.sig.generics.split_for_impl();
+    let path_const = syn::Ident::new(PATH_CONST, ident.span());
+    let method_const = syn::Ident::new(METHOD_CONST, ident.span());
+
+    quote! {
+        #[poem::handler]
+        #item
+
+        impl #impl_generics #ident #type_generics #where_clause {
+            #[doc(hidden)]
+            pub const #path_const: &'static str = #path;
+            #[doc(hidden)]
+            pub const #method_const: &'static str = #method;
+        }
+    }
+}
+
+/// The handlers given to the `collect_routes!` macro.
+pub(crate) struct CollectRoutes {
+    handlers: Punctuated<syn::Path, Token![,]>,
+}
+
+impl Parse for CollectRoutes {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        Ok(Self {
+            handlers: Punctuated::parse_terminated(input)?,
+        })
+    }
+}
+
+impl CollectRoutes {
+    /// Render a `Route` containing each of the handlers.
+    ///
+    /// The handlers are added to a method router for their path, in the order in which they are
+    /// given, and the method routers are then added to the route in the order of their first
+    /// handler. Poem would silently ignore a second handler for the same method and path, so this
+    /// panics instead, in the same way as Poem does for a duplicate path.
+    pub(crate) fn render(&self) -> proc_macro2::TokenStream {
+        let path_const = syn::Ident::new(PATH_CONST, proc_macro2::Span::call_site());
+        let method_const = syn::Ident::new(METHOD_CONST, proc_macro2::Span::call_site());
+        let handlers = self.handlers.iter().map(|handler| {
+            quote! {
+                {
+                    let path = <#handler>::#path_const;
+                    let method_name = <#handler>::#method_const;
+                    let method = poem::http::Method::from_bytes(method_name.as_bytes()).unwrap();
+                    match methods.iter_mut().find(|(other, _, _)| *other == path) {
+                        Some((_, names, route_method)) => {
+                            if names.contains(&method_name) {
+                                panic!("duplicate method `{method_name}` for route `{path}`");
+                            }
+
+                            names.push(method_name);
+                            *route_method = ::std::mem::take(route_method).method(method, #handler);
+                        }
+
+                        None => methods.push((
+                            path,
+                            ::std::vec![method_name],
+                            poem::RouteMethod::new().method(method, #handler),
+                        )),
+                    }
+                }
+            }
+        });
+
+        quote! {
+            {
+                let mut methods: ::std::vec::Vec<(
+                    &'static str,
+                    ::std::vec::Vec<&'static str>,
+                    poem::RouteMethod,
+                )> = ::std::vec::Vec::new();
+                #(#handlers)*
+                methods
+                    .into_iter()
+                    .fold(poem::Route::new(), |route, (path, _, route_method)| {
+                        route.at(path, route_method)
+                    })
+            }
+        }
+    }
+}
@@ -1,29 +1,115 @@
+use std::{borrow::Cow, collections::HashMap};
+
+use modifiers::Modifiers;
+use path::RoutePath;
 use proc_macro::TokenStream;
 use quote::{format_ident, quote, IdentFragment, ToTokens, TokenStreamExt};
 use syn::{
-    braced,
+    braced, parenthesized,
     parse::{Parse, ParseStream},
-    parse_macro_input, Token,
+    punctuated::Punctuated,
+    Token,
 };
 
+mod derive;
+mod handler;
+mod lock;
+mod manifest;
+mod modifiers;
+mod path;
+mod paths;
+mod urls;
+
+/// The endpoint of a nested route.
+enum NestedEndpoint {
+    /// A block expression that evaluates to the endpoint.
+    Block(syn::ExprBlock),
+    /// A set of routes given inline with the `routes` marker, such as `routes { "/" index GET }`.
+    Routes(Vec<Route>),
+}
+
+impl Parse for NestedEndpoint {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let lookahead = input.lookahead1();
+        if lookahead.peek(keyword::routes) {
+            input.parse::<keyword::routes>()?;
+
+            let content;
+            braced!(content in input);
+            let routes = parse_routes(&content)?;
+            check_routes(&routes)?;
+            Ok(Self::Routes(routes))
+        } else if lookahead.peek(syn::token::Brace) {
+            Ok(Self::Block(input.parse()?))
+        } else {
+            Err(lookahead.error())
+        }
+    }
+}
+
 struct NestedRoute {
-    path: syn::LitStr,
-    endpoint: syn::ExprBlock,
+    /// Whether the nested route is given with a double asterisk, and should not strip the prefix.
+    no_strip: bool,
+    path: RoutePath,
+    endpoint: NestedEndpoint,
+    modifiers: Modifiers,
 }
 
 impl Parse for NestedRoute {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         input.parse::<Token![*]>()?;
-        let path = input.parse()?;
+        let no_strip = input.parse::<Option<Token![*]>>()?.is_some();
+        let path = Self::parse_path(input)?;
         let endpoint = input.parse()?;
-        Ok(Self { path, endpoint })
+        let modifiers = input.parse()?;
+        Ok(Self {
+            no_strip,
+            path,
+            endpoint,
+            modifiers,
+        })
     }
 }
 
 impl NestedRoute {
-    fn cleanup_endpoint(&self) -> proc_macro2::TokenStream {
-        let Self { endpoint, .. } = self;
+    fn parse_path(input: ParseStream) -> syn::Result<RoutePath> {
+        let path: RoutePath = input.parse()?;
+
+        // Poem nests an endpoint by adding a wildcard to the end of the path, and does not allow
+        // the path of a nested route to have any parameters or wildcards of its own.
+        if !path.is_static() {
+            return Err(syn::Error::new(
+                path.span(),
+                "invalid route path: a nested route cannot have parameters or wildcards",
+            ));
+        }
+
+        Ok(path)
+    }
+
+    /// Parse a group of routes, such as `group "/api" with Auth { "/users" users GET }`.
+    ///
+    /// A group is the same as an inline nested route, except that the middleware is given before
+    /// the routes, so that it can be seen at the top of a long group.
+    fn parse_group(input: ParseStream) -> syn::Result<Self> {
+        input.parse::<keyword::group>()?;
+        let path = Self::parse_path(input)?;
+        let modifiers = Modifiers::parse_until(input, true)?;
+
+        let content;
+        braced!(content in input);
+        let routes = parse_routes(&content)?;
+        check_routes(&routes)?;
+
+        Ok(Self {
+            no_strip: false,
+            path,
+            endpoint: NestedEndpoint::Routes(routes),
+            modifiers,
+        })
+    }
 
+    fn cleanup_endpoint(endpoint: &syn::ExprBlock) -> proc_macro2::TokenStream {
         // This is a cheeky shortcut to avoid warnings from Clippy insisting that we remove the
         // braces around a method argument. This is because the nested endpoint might be a simple
         // expression that Clippy, quite rightly, asserts need not be wrapped in braces. To reduce
@@ -42,12 +128,32 @@ impl NestedRoute {
         }
     }
 
-    fn render(&self) -> proc_macro2::TokenStream {
-        let Self { path, .. } = self;
-        let endpoint = self.cleanup_endpoint();
+    fn render(&self, options: &Options) -> proc_macro2::TokenStream {
+        let Self {
+            no_strip,
+            path,
+            endpoint,
+            modifiers,
+        } = self;
+        let endpoint = match endpoint {
+            NestedEndpoint::Block(endpoint) => Self::cleanup_endpoint(endpoint),
+            NestedEndpoint::Routes(routes) => {
+                let routes = render_routes(routes, options);
+                quote! {
+                    poem::Route::new() #(#routes)*
+                }
+            }
+        };
 
-        quote! {
-          .nest(#path, #endpoint)
+        let endpoint = modifiers.apply(endpoint);
+        if *no_strip {
+            quote! {
+              .nest_no_strip(#path, #endpoint)
+            }
+        } else {
+            quote! {
+              .nest(#path, #endpoint)
+            }
         }
     }
 }
@@ -57,6 +163,29 @@ mod keyword {
     syn::custom_keyword!(POST);
     syn::custom_keyword!(PUT);
     syn::custom_keyword!(DELETE);
+    syn::custom_keyword!(HEAD);
+    syn::custom_keyword!(OPTIONS);
+    syn::custom_keyword!(CONNECT);
+    syn::custom_keyword!(PATCH);
+    syn::custom_keyword!(TRACE);
+    syn::custom_keyword!(ANY);
+
+    syn::custom_keyword!(naming);
+    syn::custom_keyword!(prefix);
+    syn::custom_keyword!(suffix);
+    syn::custom_keyword!(module);
+
+    syn::custom_keyword!(routes);
+    syn::custom_keyword!(paths);
+    syn::custom_keyword!(urls);
+    syn::custom_keyword!(manifest);
+    syn::custom_keyword!(json);
+    syn::custom_keyword!(lock);
+    syn::custom_keyword!(with);
+    syn::custom_keyword!(group);
+    syn::custom_keyword!(data);
+    syn::custom_keyword!(catch);
+    syn::custom_keyword!(fallback);
 }
 
 enum Method {
@@ -64,31 +193,56 @@ enum Method {
     Post(keyword::POST),
     Put(keyword::PUT),
     Delete(keyword::DELETE),
+    Head(keyword::HEAD),
+    Options(keyword::OPTIONS),
+    Connect(keyword::CONNECT),
+    Patch(keyword::PATCH),
+    Trace(keyword::TRACE),
+    Any(keyword::ANY),
+    Custom(syn::Ident),
 }
 
 impl Method {
-    fn render(&self) -> &'static str {
-        match self {
+    fn render(&self) -> Cow<'static, str> {
+        Cow::Borrowed(match self {
             Self::Get(_) => "get",
             Self::Post(_) => "post",
             Self::Put(_) => "put",
             Self::Delete(_) => "delete",
+            Self::Head(_) => "head",
+            Self::Options(_) => "options",
+            Self::Connect(_) => "connect",
+            Self::Patch(_) => "patch",
+            Self::Trace(_) => "trace",
+            Self::Any(_) => "any",
+            Self::Custom(ident) => return Cow::Owned(ident.to_string().to_lowercase()),
+        })
+    }
+
+    fn span(&self) -> proc_macro2::Span {
+        match self {
+            Self::Get(kw) => kw.span,
+            Self::Post(kw) => kw.span,
+            Self::Put(kw) => kw.span,
+            Self::Delete(kw) => kw.span,
+            Self::Head(kw) => kw.span,
+            Self::Options(kw) => kw.span,
+            Self::Connect(kw) => kw.span,
+            Self::Patch(kw) => kw.span,
+            Self::Trace(kw) => kw.span,
+            Self::Any(kw) => kw.span,
+            Self::Custom(ident) => ident.span(),
         }
     }
 }
 
 impl IdentFragment for Method {
     fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
-        core::fmt::Display::fmt(self.render(), f)
+        core::fmt::Display::fmt(&self.render(), f)
     }
 
     fn span(&self) -> Option<proc_macro2::Span> {
-        Some(match self {
-            Self::Get(kw) => kw.span,
-            Self::Post(kw) => kw.span,
-            Self::Put(kw) => kw.span,
-            Self::Delete(kw) => kw.span,
-        })
+        Some(Method::span(self))
     }
 }
 
@@ -99,6 +253,13 @@ impl ToTokens for Method {
             Self::Post(kw) => proc_macro2::Ident::new("post", kw.span),
             Self::Put(kw) => proc_macro2::Ident::new("put", kw.span),
             Self::Delete(kw) => proc_macro2::Ident::new("delete", kw.span),
+            Self::Head(kw) => proc_macro2::Ident::new("head", kw.span),
+            Self::Options(kw) => proc_macro2::Ident::new("options", kw.span),
+            Self::Connect(kw) => proc_macro2::Ident::new("connect", kw.span),
+            Self::Patch(kw) => proc_macro2::Ident::new("patch", kw.span),
+            Self::Trace(kw) => proc_macro2::Ident::new("trace", kw.span),
+            Self::Any(kw) => proc_macro2::Ident::new("any", kw.span),
+            Self::Custom(ident) => proc_macro2::Ident::new("method", ident.span()),
         };
 
         tokens.append(ident);
@@ -116,26 +277,112 @@ impl Parse for Method {
             Ok(Self::Put(input.parse::<keyword::PUT>()?))
         } else if lookahead.peek(keyword::DELETE) {
             Ok(Self::Delete(input.parse::<keyword::DELETE>()?))
+        } else if lookahead.peek(keyword::HEAD) {
+            Ok(Self::Head(input.parse::<keyword::HEAD>()?))
+        } else if lookahead.peek(keyword::OPTIONS) {
+            Ok(Self::Options(input.parse::<keyword::OPTIONS>()?))
+        } else if lookahead.peek(keyword::CONNECT) {
+            Ok(Self::Connect(input.parse::<keyword::CONNECT>()?))
+        } else if lookahead.peek(keyword::PATCH) {
+            Ok(Self::Patch(input.parse::<keyword::PATCH>()?))
+        } else if lookahead.peek(keyword::TRACE) {
+            Ok(Self::Trace(input.parse::<keyword::TRACE>()?))
+        } else if lookahead.peek(keyword::ANY) {
+            Ok(Self::Any(input.parse::<keyword::ANY>()?))
+        } else if lookahead.peek(syn::Ident) {
+            // Any other identifier is taken to be an extension method, such as the WebDAV methods
+            // `PROPFIND` or `MKCOL`. These are passed to Poem by name, so we only accept those that
+            // look like an HTTP method: upper-case letters, digits and underscores.
+            let ident = input.parse::<syn::Ident>()?;
+            let name = ident.to_string();
+            if !name
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+            {
+                return Err(syn::Error::new(
+                    ident.span(),
+                    format!("invalid HTTP method `{name}`: methods must be upper-case"),
+                ));
+            }
+
+            Ok(Self::Custom(ident))
         } else {
             Err(lookahead.error())
         }
     }
 }
 
-struct StandardRoute {
-    path: syn::LitStr,
-    ident: syn::Path,
-    methods: Vec<Method>,
+/// A method of a route, along with any middleware that applies to that method alone, such as
+/// `POST(with Csrf::new())`.
+struct MethodEntry {
+    method: Method,
+    modifiers: Modifiers,
 }
 
-impl Parse for StandardRoute {
+impl Parse for MethodEntry {
     fn parse(input: ParseStream) -> syn::Result<Self> {
-        let path = input.parse()?;
+        let method = input.parse()?;
+        if !input.peek(syn::token::Paren) {
+            return Ok(Self {
+                method,
+                modifiers: Modifiers::default(),
+            });
+        }
+
+        let content;
+        parenthesized!(content in input);
+        let modifiers: Modifiers = content.parse()?;
+        if modifiers.is_empty() {
+            return Err(content.error("expected `with`, `data` or `catch`"));
+        }
+
+        if !content.is_empty() {
+            return Err(content.error("unexpected token after the modifiers"));
+        }
+
+        Ok(Self { method, modifiers })
+    }
+}
+
+/// An explicit mapping from a method to a handler, such as `GET => users::list`.
+struct MethodHandler {
+    method: MethodEntry,
+    handler: syn::Path,
+}
+
+impl Parse for MethodHandler {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let method = input.parse()?;
+        input.parse::<Token![=>]>()?;
+        let handler = input.parse()?;
+        Ok(Self { method, handler })
+    }
+}
+
+/// The handlers for a standard route.
+enum Handlers {
+    /// A handler name template, from which the handler for each method is derived.
+    Template {
+        ident: syn::Path,
+        methods: Vec<MethodEntry>,
+    },
+    /// An explicit handler for each method.
+    Explicit(Punctuated<MethodHandler, Token![,]>),
+}
+
+impl Parse for Handlers {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(syn::token::Brace) {
+            let content;
+            braced!(content in input);
+            return Ok(Self::Explicit(Punctuated::parse_terminated(&content)?));
+        }
+
         let ident = input.parse()?;
         let methods = {
             let mut methods = Vec::new();
 
-            while !input.is_empty() {
+            while !input.is_empty() && !Modifiers::peek(input) && !input.peek(keyword::group) {
                 let lookahead = input.lookahead1();
                 if !lookahead.peek(syn::Ident) {
                     break;
@@ -147,56 +394,247 @@ impl Parse for StandardRoute {
             methods
         };
 
+        Ok(Self::Template { ident, methods })
+    }
+}
+
+impl Handlers {
+    fn methods(&self) -> Vec<&Method> {
+        match self {
+            Self::Template { methods, .. } => methods.iter().map(|entry| &entry.method).collect(),
+            Self::Explicit(handlers) => handlers
+                .iter()
+                .map(|handler| &handler.method.method)
+                .collect(),
+        }
+    }
+
+    /// Resolve the handler for each method, applying the method to the handler name template if
+    /// necessary. The `ANY` method uses the handler name template without modification.
+    fn resolve(&self, naming: Naming) -> Vec<(&MethodEntry, syn::Path)> {
+        match self {
+            Self::Template { ident, methods } => methods
+                .iter()
+                .map(|entry| match &entry.method {
+                    Method::Any(_) => (entry, ident.clone()),
+                    method => (entry, apply_method_path(naming, ident, method)),
+                })
+                .collect(),
+            Self::Explicit(handlers) => handlers
+                .iter()
+                .map(|handler| (&handler.method, handler.handler.clone()))
+                .collect(),
+        }
+    }
+}
+
+struct StandardRoute {
+    path: RoutePath,
+    handlers: Handlers,
+    modifiers: Modifiers,
+}
+
+impl Parse for StandardRoute {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let path: RoutePath = input.parse()?;
+        let handlers: Handlers = input.parse()?;
+
+        if handlers.methods().is_empty() {
+            return Err(syn::Error::new(path.span(), "expected at least one method"));
+        }
+
+        let modifiers = input.parse()?;
         Ok(Self {
             path,
-            ident,
-            methods,
+            handlers,
+            modifiers,
         })
     }
 }
 
-fn apply_method(ident: &syn::Ident, method: &Method) -> syn::Ident {
-    format_ident!("{}_{}", method, ident)
+/// The strategy used to derive a handler name from a handler name template and a method.
+#[derive(Clone, Copy, Default)]
+enum Naming {
+    /// Prefix the method to the last segment: `paste` becomes `get_paste`.
+    #[default]
+    Prefix,
+    /// Suffix the method to the last segment: `paste` becomes `paste_get`.
+    Suffix,
+    /// Insert a module named after the method before the last segment: `paste` becomes
+    /// `get::paste`.
+    Module,
+}
+
+impl Parse for Naming {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let lookahead = input.lookahead1();
+        if lookahead.peek(keyword::prefix) {
+            input.parse::<keyword::prefix>()?;
+            Ok(Self::Prefix)
+        } else if lookahead.peek(keyword::suffix) {
+            input.parse::<keyword::suffix>()?;
+            Ok(Self::Suffix)
+        } else if lookahead.peek(keyword::module) {
+            input.parse::<keyword::module>()?;
+            Ok(Self::Module)
+        } else {
+            Err(lookahead.error())
+        }
+    }
 }
 
-fn apply_method_path(head: bool, path: &syn::Path, method: &Method) -> proc_macro2::TokenStream {
+/// Derive the name of a handler from a handler name template and a method.
+fn apply_method_path(naming: Naming, path: &syn::Path, method: &Method) -> syn::Path {
     let mut path = path.clone();
-    if let Some(last) = path.segments.last_mut() {
-        last.ident = apply_method(&last.ident, method)
+    let Some(last) = path.segments.pop() else {
+        return path;
+    };
+
+    let mut last = last.into_value();
+    match naming {
+        Naming::Prefix => last.ident = format_ident!("{}_{}", method, last.ident),
+        Naming::Suffix => last.ident = format_ident!("{}_{}", last.ident, method),
+        Naming::Module => path
+            .segments
+            .push(syn::PathSegment::from(format_ident!("{}", method))),
+    }
+
+    path.segments.push(last);
+    path
+}
+
+fn render_method(
+    head: bool,
+    method: &Method,
+    handler: &proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    if let Method::Custom(ident) = method {
+        // Extension methods don't have a dedicated function in Poem, so we go through the general
+        // `RouteMethod::method`. The name has already been checked when parsing, so the conversion
+        // to an `http::Method` cannot fail.
+        let name = syn::LitByteStr::new(ident.to_string().as_bytes(), ident.span());
+        let args = quote! {
+            poem::http::Method::from_bytes(#name).unwrap(), #handler
+        };
+
+        return if head {
+            quote! {
+              poem::RouteMethod::new().#method(#args)
+            }
+        } else {
+            quote! {
+              . #method(#args)
+            }
+        };
     }
 
     if head {
         quote! {
-          poem::#method(#path)
+          poem::#method(#handler)
         }
     } else {
         quote! {
-          . #method(#path)
+          . #method(#handler)
         }
     }
 }
 
-impl StandardRoute {
-    fn render(&self) -> proc_macro2::TokenStream {
-        let Self {
-            path,
-            ident,
-            methods,
-        } = self;
+/// Render all the standard routes that share the same path into a single call to `Route::at`,
+/// merging their methods into one method router.
+fn render_standard(routes: &[&StandardRoute], options: &Options) -> proc_macro2::TokenStream {
+    let path = &routes[0].path;
+    let handlers = routes
+        .iter()
+        .flat_map(|route| route.handlers.resolve(options.naming))
+        .collect::<Vec<_>>();
+
+    // Any middleware for a single method wraps the handler for that method.
+    let handlers = handlers
+        .into_iter()
+        .map(|(entry, handler)| {
+            let handler = entry.modifiers.apply(quote! {
+              #handler
+            });
+            (&entry.method, handler)
+        })
+        .collect::<Vec<_>>();
 
+    // A route with the `ANY` method uses the handler directly as the endpoint.
+    let endpoint = if let [(Method::Any(_), handler)] = handlers.as_slice() {
+        quote! {
+          #handler
+        }
+    } else {
         let mut builder = Vec::new();
-        for method in methods {
-            builder.push(apply_method_path(builder.is_empty(), ident, method));
+        for (method, handler) in &handlers {
+            builder.push(render_method(builder.is_empty(), method, handler));
         }
 
         quote! {
-          .at(#path, #(#builder)*)
+          #(#builder)*
+        }
+    };
+
+    // The modifiers wrap the whole method router, and at most one of the routes can have them.
+    let endpoint = match routes.iter().find(|route| !route.modifiers.is_empty()) {
+        Some(route) => route.modifiers.apply(endpoint),
+        None => endpoint,
+    };
+
+    quote! {
+      .at(#path, #endpoint)
+    }
+}
+
+/// Prepend a module to a handler path.
+///
+/// Paths that are already absolute, such as `::foo` or `crate::foo`, or that are relative to the
+/// current module, such as `self::foo` or `super::foo`, are left as they are.
+fn scope_path(module: &syn::Path, path: &mut syn::Path) {
+    if path.leading_colon.is_some() {
+        return;
+    }
+
+    if let Some(first) = path.segments.first() {
+        if first.ident == "crate" || first.ident == "self" || first.ident == "super" {
+            return;
         }
     }
+
+    let mut scoped = module.clone();
+    scoped.segments.extend(path.segments.iter().cloned());
+    *path = scoped;
+}
+
+/// A group of routes whose handlers are all found in the same module, such as
+/// `mod admin { "/admin/users" users GET }`.
+///
+/// The module is prepended to the handler of every route in the group when it is parsed, so the
+/// routes are rendered as if they had been written with the module in place.
+struct ScopedRoutes {
+    routes: Vec<Route>,
+}
+
+impl Parse for ScopedRoutes {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        input.parse::<Token![mod]>()?;
+        let module = input.call(syn::Path::parse_mod_style)?;
+
+        let content;
+        braced!(content in input);
+        let mut routes = parse_routes(&content)?;
+
+        for route in &mut routes {
+            route.scope(&module);
+        }
+
+        Ok(Self { routes })
+    }
 }
 
 enum Route {
     Nested(NestedRoute),
+    Scoped(ScopedRoutes),
     Standard(StandardRoute),
 }
 
@@ -205,6 +643,10 @@ impl Parse for Route {
         let lookahead = input.lookahead1();
         if lookahead.peek(Token![*]) {
             Ok(Self::Nested(input.parse()?))
+        } else if lookahead.peek(Token![mod]) {
+            Ok(Self::Scoped(input.parse()?))
+        } else if lookahead.peek(keyword::group) {
+            Ok(Self::Nested(NestedRoute::parse_group(input)?))
         } else {
             Ok(Self::Standard(input.parse()?))
         }
@@ -212,24 +654,457 @@ impl Parse for Route {
 }
 
 impl Route {
-    fn render(&self) -> proc_macro2::TokenStream {
+    /// Prepend the given module to the handlers of this route.
+    fn scope(&mut self, module: &syn::Path) {
+        match self {
+            Self::Nested(nested) => {
+                if let NestedEndpoint::Routes(routes) = &mut nested.endpoint {
+                    for route in routes {
+                        route.scope(module);
+                    }
+                }
+            }
+
+            Self::Scoped(scoped) => {
+                for route in &mut scoped.routes {
+                    route.scope(module);
+                }
+            }
+
+            Self::Standard(standard) => match &mut standard.handlers {
+                Handlers::Template { ident, .. } => scope_path(module, ident),
+                Handlers::Explicit(handlers) => {
+                    for handler in handlers {
+                        scope_path(module, &mut handler.handler);
+                    }
+                }
+            },
+        }
+    }
+}
+
+fn parse_routes(input: ParseStream) -> syn::Result<Vec<Route>> {
+    let mut routes = Vec::new();
+
+    while !input.is_empty() {
+        routes.push(input.parse()?);
+    }
+
+    Ok(routes)
+}
+
+/// A single call on a `Route`: either a nested route, or all of the standard routes that share the
+/// same path.
+enum RouteEntry<'a> {
+    Nested(&'a NestedRoute),
+    Standard(Vec<&'a StandardRoute>),
+}
+
+impl RouteEntry<'_> {
+    fn render(&self, options: &Options) -> proc_macro2::TokenStream {
         match self {
-            Self::Nested(nested) => nested.render(),
-            Self::Standard(standard) => standard.render(),
+            Self::Nested(nested) => nested.render(options),
+            Self::Standard(routes) => render_standard(routes, options),
         }
     }
 }
 
+/// Collect the calls that the given routes will make on a single `Route`. Scoped routes are added to
+/// the same `Route`, and standard routes with the same path are merged together, in the position
+/// of the first of them.
+fn collect_entries<'a>(routes: &'a [Route], entries: &mut Vec<RouteEntry<'a>>) {
+    for route in routes {
+        match route {
+            Route::Nested(nested) => entries.push(RouteEntry::Nested(nested)),
+            Route::Scoped(scoped) => collect_entries(&scoped.routes, entries),
+            Route::Standard(standard) => {
+                let value = standard.path.lit.value();
+                let group = entries.iter_mut().find_map(|entry| match entry {
+                    RouteEntry::Standard(group) if group[0].path.lit.value() == value => {
+                        Some(group)
+                    }
+                    _ => None,
+                });
+
+                match group {
+                    Some(group) => group.push(standard),
+                    None => entries.push(RouteEntry::Standard(vec![standard])),
+                }
+            }
+        }
+    }
+}
+
+fn render_routes(routes: &[Route], options: &Options) -> Vec<proc_macro2::TokenStream> {
+    let mut entries = Vec::new();
+    collect_entries(routes, &mut entries);
+    entries.iter().map(|entry| entry.render(options)).collect()
+}
+
+/// A standard route, along with the full path of the route, including the paths of any enclosing
+/// nested routes.
+struct FlatRoute<'a> {
+    /// The paths of the enclosing nested routes, joined together.
+    prefix: String,
+    /// The full path of the route.
+    path: String,
+    route: &'a StandardRoute,
+}
+
+/// Join the path of a nested route with the path of a route within it.
+fn join_paths(prefix: &str, path: &str) -> String {
+    let prefix = prefix.trim_end_matches('/');
+    match path {
+        "/" if !prefix.is_empty() => prefix.to_string(),
+        _ => format!("{prefix}{path}"),
+    }
+}
+
+/// Collect all the standard routes, including those within scoped routes and inline nested routes,
+/// along with their full paths.
+///
+/// A nested route that does not strip its prefix expects the routes within it to include the
+/// prefix already, so its path is not added to the full path of the routes within it.
+fn flatten_routes<'a>(
+    routes: &'a [Route],
+    prefix: &str,
+    path_prefix: &str,
+    flat: &mut Vec<FlatRoute<'a>>,
+) {
+    for route in routes {
+        match route {
+            Route::Nested(nested) => {
+                if let NestedEndpoint::Routes(routes) = &nested.endpoint {
+                    let path = nested.path.lit.value();
+                    let nested_prefix = join_paths(prefix, &path);
+                    if nested.no_strip {
+                        flatten_routes(routes, &nested_prefix, path_prefix, flat);
+                    } else {
+                        flatten_routes(
+                            routes,
+                            &nested_prefix,
+                            &join_paths(path_prefix, &path),
+                            flat,
+                        );
+                    }
+                }
+            }
+
+            Route::Scoped(scoped) => flatten_routes(&scoped.routes, prefix, path_prefix, flat),
+            Route::Standard(standard) => flat.push(FlatRoute {
+                prefix: prefix.to_string(),
+                path: join_paths(path_prefix, &standard.path.lit.value()),
+                route: standard,
+            }),
+        }
+    }
+}
+
+/// Add an error for a conflict between two parts of the routes, spanned at both.
+fn push_conflict(
+    error: &mut Option<syn::Error>,
+    (span, message): (proc_macro2::Span, String),
+    (first_span, first_message): (proc_macro2::Span, String),
+) {
+    let mut conflict = syn::Error::new(span, message);
+    conflict.combine(syn::Error::new(first_span, first_message));
+
+    match error {
+        Some(error) => error.combine(conflict),
+        None => *error = Some(conflict),
+    }
+}
+
+/// Check that each method is only given once for a path, and that the `ANY` method is not combined
+/// with any other method.
+fn check_methods(routes: &[&StandardRoute], error: &mut Option<syn::Error>) {
+    let value = routes[0].path.lit.value();
+    let methods = routes
+        .iter()
+        .flat_map(|route| route.handlers.methods())
+        .collect::<Vec<_>>();
+
+    // The `ANY` method routes every method to the one handler, so it makes no sense to combine it
+    // with any other method.
+    let any = methods
+        .iter()
+        .copied()
+        .find(|method| matches!(method, Method::Any(_)));
+    let other = methods
+        .iter()
+        .copied()
+        .find(|method| !matches!(method, Method::Any(_)));
+
+    if let (Some(any), Some(other)) = (any, other) {
+        push_conflict(
+            error,
+            (
+                any.span(),
+                "the `ANY` method cannot be combined with other methods".to_string(),
+            ),
+            (
+                other.span(),
+                format!("other method given for `{value}` here"),
+            ),
+        );
+
+        return;
+    }
+
+    // The modifiers of a route wrap the method router for its path, so they apply to all of the
+    // methods of the path, even those given by another route with the same path.
+    let mut modified = routes.iter().filter(|route| !route.modifiers.is_empty());
+    if let (Some(first), Some(second)) = (modified.next(), modified.next()) {
+        push_conflict(
+            error,
+            (
+                second.path.span(),
+                format!(
+                    "the `with`, `data` and `catch` clauses for `{value}` can only be given on one \
+                     of its routes, as they apply to all of the methods of the path"
+                ),
+            ),
+            (
+                first.path.span(),
+                format!("clauses for `{value}` are first given here"),
+            ),
+        );
+    }
+
+    for (index, &method) in methods.iter().enumerate() {
+        let name = method.render();
+        if let Some(&first) = methods[..index].iter().find(|first| first.render() == name) {
+            let name = name.to_uppercase();
+            push_conflict(
+                error,
+                (
+                    method.span(),
+                    format!("duplicate method `{name}` for route `{value}`"),
+                ),
+                (first.span(), format!("method `{name}` is first given here")),
+            );
+        }
+    }
+}
+
+/// Check a set of routes for conflicting routes and methods, which would otherwise cause Poem to
+/// panic when the routes are built, or would never be matched.
+fn check_routes(routes: &[Route]) -> syn::Result<()> {
+    let mut error = None;
+
+    let mut entries = Vec::new();
+    collect_entries(routes, &mut entries);
+
+    // Gather the paths that each entry will add to the `Route`, along with the structure of each
+    // path. A nested route adds both its path and a wildcard under its path.
+    let mut paths = Vec::new();
+    for entry in &entries {
+        match entry {
+            RouteEntry::Nested(nested) => {
+                let pattern = nested.path.pattern();
+                paths.push((format!("{}/*", pattern.trim_end_matches('/')), &nested.path));
+                paths.push((pattern, &nested.path));
+            }
+
+            RouteEntry::Standard(routes) => {
+                paths.push((routes[0].path.pattern(), &routes[0].path));
+                check_methods(routes, &mut error);
+            }
+        }
+    }
+
+    let mut seen: HashMap<&str, &RoutePath> = HashMap::new();
+    let mut reported: Vec<&RoutePath> = Vec::new();
+    for (pattern, path) in &paths {
+        let Some(&first) = seen.get(pattern.as_str()) else {
+            seen.insert(pattern, path);
+            continue;
+        };
+
+        // A nested route adds two paths, so only report the first conflict for each route.
+        if reported.iter().any(|&other| std::ptr::eq(other, *path)) {
+            continue;
+        }
+
+        reported.push(path);
+
+        let value = path.lit.value();
+        let first_value = first.lit.value();
+        if value == first_value {
+            push_conflict(
+                &mut error,
+                (path.span(), format!("duplicate route `{value}`")),
+                (
+                    first.span(),
+                    format!("route `{value}` is first defined here"),
+                ),
+            );
+        } else {
+            push_conflict(
+                &mut error,
+                (
+                    path.span(),
+                    format!("route `{value}` conflicts with route `{first_value}`"),
+                ),
+                (
+                    first.span(),
+                    format!("route `{first_value}` is defined here"),
+                ),
+            );
+        }
+    }
+
+    match error {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
+
+/// Options given to the macro before the routes, such as `naming = suffix`.
+#[derive(Default)]
+struct Options {
+    naming: Naming,
+    /// The name of the module in which to generate constants for the route paths.
+    paths: Option<syn::Ident>,
+    /// The name of the module in which to generate URL builder functions for the routes.
+    urls: Option<syn::Ident>,
+    /// The name of the module in which to generate a manifest of the routes.
+    manifest: Option<syn::Ident>,
+    /// The path of the file to which a JSON manifest of the routes is written.
+    json: Option<syn::LitStr>,
+    /// The path of the lock file against which the routes are checked.
+    lock: Option<syn::LitStr>,
+}
+
+impl Options {
+    fn peek(input: ParseStream) -> bool {
+        (input.peek(keyword::naming)
+            || input.peek(keyword::paths)
+            || input.peek(keyword::urls)
+            || input.peek(keyword::manifest)
+            || input.peek(keyword::json)
+            || input.peek(keyword::lock))
+            && input.peek2(Token![=])
+    }
+}
+
+impl Options {
+    /// Parse a single `key = value` option.
+    fn parse_option(&mut self, input: ParseStream) -> syn::Result<()> {
+        let lookahead = input.lookahead1();
+        if lookahead.peek(keyword::naming) {
+            input.parse::<keyword::naming>()?;
+            input.parse::<Token![=]>()?;
+            self.naming = input.parse()?;
+        } else if lookahead.peek(keyword::paths) {
+            input.parse::<keyword::paths>()?;
+            input.parse::<Token![=]>()?;
+            self.paths = Some(input.parse()?);
+        } else if lookahead.peek(keyword::urls) {
+            input.parse::<keyword::urls>()?;
+            input.parse::<Token![=]>()?;
+            self.urls = Some(input.parse()?);
+        } else if lookahead.peek(keyword::manifest) {
+            input.parse::<keyword::manifest>()?;
+            input.parse::<Token![=]>()?;
+            self.manifest = Some(input.parse()?);
+        } else if lookahead.peek(keyword::json) {
+            input.parse::<keyword::json>()?;
+            input.parse::<Token![=]>()?;
+            self.json = Some(input.parse()?);
+        } else if lookahead.peek(keyword::lock) {
+            input.parse::<keyword::lock>()?;
+            input.parse::<Token![=]>()?;
+            self.lock = Some(input.parse()?);
+        } else {
+            return Err(lookahead.error());
+        }
+
+        Ok(())
+    }
+
+    /// Parse the options given as the arguments of the `routes` attribute, which are separated by
+    /// commas, with an optional trailing comma.
+    fn parse_args(input: ParseStream) -> syn::Result<Self> {
+        let mut options = Self::default();
+
+        while !input.is_empty() {
+            options.parse_option(input)?;
+            if input.is_empty() {
+                break;
+            }
+
+            input.parse::<Token![,]>()?;
+        }
+
+        Ok(options)
+    }
+}
+
+impl Parse for Options {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut options = Self::default();
+
+        while Self::peek(input) {
+            options.parse_option(input)?;
+            input.parse::<Token![,]>()?;
+        }
+
+        Ok(options)
+    }
+}
+
+/// The signature of a function in which to define the routes, such as `pub fn routes() -> Route`.
+struct RoutesFn {
+    attrs: Vec<syn::Attribute>,
+    vis: syn::Visibility,
+    sig: syn::Signature,
+}
+
+impl RoutesFn {
+    /// Whether the input starts with a function signature, after any attributes and visibility,
+    /// following the leading tokens of `syn::Signature`, such as `pub async fn` or
+    /// `unsafe extern "C" fn`.
+    fn peek(input: ParseStream) -> bool {
+        let fork = input.fork();
+        let leading = || -> syn::Result<()> {
+            fork.call(syn::Attribute::parse_outer)?;
+            fork.parse::<syn::Visibility>()?;
+            fork.parse::<Option<Token![const]>>()?;
+            fork.parse::<Option<Token![async]>>()?;
+            fork.parse::<Option<Token![unsafe]>>()?;
+            fork.parse::<Option<syn::Abi>>()?;
+            Ok(())
+        };
+
+        leading().is_ok() && fork.peek(Token![fn])
+    }
+}
+
+impl Parse for RoutesFn {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(syn::Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        let sig = input.parse()?;
+        Ok(Self { attrs, vis, sig })
+    }
+}
+
 struct Routes {
+    options: Options,
     route: proc_macro2::TokenStream,
+    function: Option<RoutesFn>,
     routes: Vec<Route>,
+    /// The modifiers given after the routes, which apply to all of the routes.
+    modifiers: Modifiers,
 }
 
 impl Parse for Routes {
     fn parse(input: ParseStream) -> syn::Result<Self> {
+        let options: Options = input.parse()?;
         let route = {
             let lookahead = input.lookahead1();
-            if lookahead.peek(syn::token::Brace) {
+            if lookahead.peek(syn::token::Brace) || RoutesFn::peek(input) {
                 quote! {
                     poem::Route::new()
                 }
@@ -243,26 +1118,167 @@ impl Parse for Routes {
             }
         };
 
-        let content;
-        braced!(content in input);
-        let mut routes = Vec::new();
+        let function = if RoutesFn::peek(input) {
+            Some(input.parse()?)
+        } else {
+            None
+        };
 
-        while !content.is_empty() {
-            routes.push(content.parse()?);
+        // The generated path constants, URL builders and manifest are items, which are only
+        // visible outside of the macro if it is used to define a function.
+        if function.is_none() {
+            for (option, module) in [
+                ("paths", &options.paths),
+                ("urls", &options.urls),
+                ("manifest", &options.manifest),
+            ] {
+                if let Some(module) = module {
+                    return Err(syn::Error::new(
+                        module.span(),
+                        format!(
+                            "the `{option}` option requires the routes to be defined in a \
+                             function, such as `fn routes() -> Route {{ ... }}`"
+                        ),
+                    ));
+                }
+            }
         }
 
-        Ok(Self { route, routes })
+        Self::parse_body(input, options, route, function)
     }
 }
 
 impl Routes {
-    fn render(&self) -> proc_macro2::TokenStream {
-        let Self { route, routes } = self;
-        let routes = routes.iter().map(Route::render);
+    /// Parse the braced routes that follow the options, the initial route and the function
+    /// signature.
+    fn parse_body(
+        input: ParseStream,
+        options: Options,
+        route: proc_macro2::TokenStream,
+        function: Option<RoutesFn>,
+    ) -> syn::Result<Self> {
+        let content;
+        braced!(content in input);
+        let routes = parse_routes(&content)?;
+        check_routes(&routes)?;
+        let modifiers = Modifiers::parse_top_level(input)?;
 
-        quote! {
-          #route #(#routes)*
+        Ok(Self {
+            options,
+            route,
+            function,
+            routes,
+            modifiers,
+        })
+    }
+
+    /// Parse a function to which the `routes` attribute has been applied.
+    ///
+    /// The body of the function must be valid Rust syntax, as it is parsed by the compiler before
+    /// the attribute is expanded, so the routes are wrapped in `routes! { ... }`.
+    fn parse_item(input: ParseStream, options: Options) -> syn::Result<Self> {
+        let function = input.parse()?;
+
+        let content;
+        braced!(content in input);
+        let mac: syn::Macro = content.parse()?;
+        content.parse::<Option<Token![;]>>()?;
+
+        if !mac.path.is_ident("routes") || !content.is_empty() {
+            return Err(syn::Error::new_spanned(
+                &mac.path,
+                "expected the body of the function to be `routes! { ... }`",
+            ));
         }
+
+        let routes = mac.parse_body_with(parse_routes)?;
+        check_routes(&routes)?;
+
+        Ok(Self {
+            options,
+            route: quote! {
+                poem::Route::new()
+            },
+            function: Some(function),
+            routes,
+            modifiers: Modifiers::default(),
+        })
+    }
+
+    fn render(&self) -> syn::Result<proc_macro2::TokenStream> {
+        let Self {
+            options,
+            route,
+            function,
+            routes,
+            modifiers,
+        } = self;
+        let mut flat = Vec::new();
+        flatten_routes(routes, "", "", &mut flat);
+
+        let json = match (&options.json, &options.lock) {
+            (None, None) => None,
+            _ => {
+                let records = flat
+                    .iter()
+                    .map(|route| manifest::RouteRecord::new(route, options.naming))
+                    .collect::<Vec<_>>();
+                let mut nested = Vec::new();
+                manifest::collect_nested(routes, "", &mut nested);
+                Some(manifest::render_json(&records, &nested))
+            }
+        };
+
+        if let (Some(path), Some(json)) = (&options.json, &json) {
+            manifest::write_json(path, json)?;
+        }
+
+        let rendered = render_routes(routes, options);
+        let router = modifiers.apply(quote! {
+          #route #(#rendered)*
+        });
+
+        let router = match (&options.lock, &json) {
+            (Some(path), Some(json)) => {
+                let tracking = lock::check_lock(path, json)?;
+                quote! {{
+                    #tracking
+                    #router
+                }}
+            }
+
+            _ => router,
+        };
+
+        let Some(RoutesFn { attrs, vis, sig }) = function else {
+            return Ok(router);
+        };
+
+        let paths = match &options.paths {
+            Some(module) => paths::render_paths(vis, module, &flat)?,
+            None => proc_macro2::TokenStream::new(),
+        };
+
+        let urls = match &options.urls {
+            Some(module) => urls::render_urls(vis, module, &flat)?,
+            None => proc_macro2::TokenStream::new(),
+        };
+
+        let manifest = match &options.manifest {
+            Some(module) => manifest::render_manifest(vis, module, &flat, options.naming),
+            None => proc_macro2::TokenStream::new(),
+        };
+
+        Ok(quote! {
+            #(#attrs)*
+            #vis #sig {
+                #router
+            }
+
+            #paths
+            #urls
+            #manifest
+        })
     }
 }
 
@@ -279,6 +1295,75 @@ impl Routes {
 /// The name of a route can be a qualified identifier, such as "module::foo". Any method-specific
 /// modifications are applied to the last identifier in the path: "module::get_foo".
 ///
+/// Methods other than the standard HTTP methods, such as the WebDAV `PROPFIND` and `MKCOL`, can be
+/// given in upper-case and are routed with `RouteMethod::method`. The handler name is formed in the
+/// same way, so `collection PROPFIND` expects a handler named `propfind_collection`.
+///
+/// The special `ANY` method routes all methods to a single handler. In this case the route
+/// identifier is used as-is, without a method prefix, and no other methods may be given.
+///
+/// Rather than deriving the handler names from the route identifier, each method can be mapped to
+/// a handler explicitly by giving a braced list of `METHOD => handler` pairs after the path string,
+/// such as `"/users" { GET => users::list, POST => users::create }`.
+///
+/// The way in which handler names are formed can be changed by giving a `naming` option before the
+/// routes. The default, `naming = prefix`, generates `get_foo`. With `naming = suffix` the method
+/// is appended instead, generating `foo_get`, and with `naming = module` the method is inserted as
+/// a module before the last identifier, generating `get::foo`.
+///
+/// Routes whose handlers are all in the same module can be grouped with `mod`, such as
+/// `mod admin { "/admin/users" users GET }`. The module is prepended to each handler in the group,
+/// so this will use the handler `admin::get_users`. The paths of the routes are not changed.
+///
+/// Middleware can be applied to a standard or nested route with a `with` clause after the route,
+/// such as `"/upload" upload POST with SizeLimit::new(1 << 20), Tracing`. The middleware is applied
+/// in the order given, wrapping the method router or nested endpoint. Middleware for a single
+/// method is given in parentheses after the method, such as `POST(with Csrf::new())`, and wraps
+/// the handler for that method alone. Each expression ends at a comma, or at anything that could
+/// start the next route, so an expression containing a comma outside of brackets, or a `*`, must be
+/// wrapped in parentheses, as must a block after a complete term in a group.
+///
+/// Data can be added to the requests of a route, method or group with `data(...)`, in the same
+/// places as middleware, such as `"/uploads" uploads POST data(upload_dir.clone())`. Middleware and
+/// data can also be given after the routes, such as `define_routes!({ ... } data(pool))`, in which
+/// case they apply to all of the routes.
+///
+/// Errors of a specific type can be handled with a `catch` clause in the same places, such as
+/// `catch NotFoundError => not_found_page`, using `EndpointExt::catch_error`. A `fallback` clause
+/// after all of the routes, such as `fallback => error_page`, handles any other error using
+/// `EndpointExt::catch_all_error`.
+///
+/// Routes that share a path prefix and middleware can be grouped with `group`, such as
+/// `group "/api/v1" with Auth { "/users" users GET }`. The routes in the group are built into their
+/// own `Route`, which is wrapped in the middleware and nested under the prefix.
+///
+/// The macro can also define a function that returns the routes, by giving the signature of the
+/// function before the routes, such as `define_routes!(pub fn routes() -> Route { ... })`. The
+/// function can have any attributes, visibility and qualifiers, such as `pub(crate) async fn`. In
+/// this case the macro is used in item position. When defining a function, the `paths` option can be
+/// used to generate a module of constants for the path of each route:
+///
+/// ```ignore
+/// define_routes!(paths = paths, pub fn build_routes() -> Route {
+///     "/pastes/:id"  paste  GET
+/// });
+///
+/// assert_eq!(paths::PASTE, "/pastes/:id");
+/// ```
+///
+/// Similarly, the `urls` option generates a module of functions that build the URL for each route,
+/// taking an argument for each parameter in the path, such as `urls::paste(42)` for the above.
+/// The `manifest` option generates a module with a `ROUTES` slice describing each of the routes.
+///
+/// The `json` option writes a description of the routes to a JSON file when the macro is expanded,
+/// such as `json = "routes.json"`. A relative path is resolved against the directory given in the
+/// `POEM_ROUTES_DIR` environment variable, or the `OUT_DIR` of the crate, or else the directory of
+/// the crate. This option does not require the macro to define a function.
+///
+/// The `lock` option checks the routes against a lock file, relative to the directory of the crate,
+/// and reports any routes that have been added, removed or changed as a compile error. Building with
+/// the `POEM_ROUTES_UPDATE` environment variable set to `1` rewrites the lock file instead.
+///
 /// Routes can also be nested by prefixing the route string with an asterisk. In this case, a block
 /// expression is expected after the path string.
 ///
@@ -313,25 +1398,478 @@ impl Routes {
 /// grammar simple. If the braces are not really needed, they will be stripped from the generated
 /// code.
 ///
+/// Nested routes strip the path prefix from the request before it is passed to the endpoint. If the
+/// nested endpoint needs to see the full path, a double asterisk can be used instead, which will
+/// nest the endpoint with `Route::nest_no_strip`.
+///
+/// Instead of a block expression, a nested route can give its routes inline by using the `routes`
+/// marker before the braces. The routes are added to a new `Route`, which is then nested:
+///
+/// ```ignore
+/// define_routes!({
+///     *"/admin" routes {
+///         "/"       admin::index  GET
+///         "/users"  admin::users  GET POST
+///     }
+/// })
+/// ```
+///
+/// The path strings are checked when the macro is expanded, and a malformed path, such as one
+/// without a leading slash or with a wildcard that is not at the end, is reported as a compile
+/// error.
+///
+/// If the same path is given for more than one route, the methods of those routes are merged into a
+/// single method router. The routes are checked for paths that are added more than once, such as a
+/// nested route with the same path as another route, for paths that differ only in the names of
+/// their parameters, such as `"/:id"` and `"/:name"`, and for methods that are given more than once
+/// for the same path. These are reported as compile errors at both of the offending routes.
+///
 /// The grammar for the route specification is as follows:
 ///
 /// ```plain
+/// body := { option "," } [ EXPR "," ] [ function ] "{" routes "}" { modifier | fallback }
+///
+/// fallback := "fallback" "=>" EXPR
+///
+/// option := "naming" "=" ( "prefix" | "suffix" | "module" )
+///         | "paths" "=" IDENT
+///         | "urls" "=" IDENT
+///         | "manifest" "=" IDENT
+///         | "json" "=" LIT_STR
+///         | "lock" "=" LIT_STR
+///
+/// function := { ATTRIBUTE } VISIBILITY SIGNATURE
+///
 /// routes := route { route }
 ///
-/// route := nested-route | plain-route
+/// route := nested-route | scoped-routes | route-group | plain-route
 ///
-/// nested-route := "*" LIT_STR EXPR_BLOCK
+/// nested-route := ( "*" | "**" ) LIT_STR ( EXPR_BLOCK | "routes" "{" routes "}" ) modifiers
 ///
-/// plain-route := LIT_STR path methods
+/// scoped-routes := "mod" path "{" routes "}"
+///
+/// route-group := "group" LIT_STR modifiers "{" routes "}"
+///
+/// plain-route := LIT_STR path methods modifiers
+///              | LIT_STR "{" method-handler { "," method-handler } [ "," ] "}" modifiers
+///
+/// modifiers := { modifier }
+///
+/// modifier := "with" EXPR { "," EXPR }
+///           | "data" "(" EXPR ")"
+///           | "catch" TYPE "=>" EXPR
+///
+/// method-handler := method-entry "=>" path
 ///
 /// path := IDENT { "::" IDENT }
 ///
-/// methods := method { method }
+/// methods := method-entry { method-entry }
+///
+/// method-entry := method [ "(" modifier { modifier } ")" ]
 ///
-/// method := "GET" | "POST" | "PUT" | "DELETE"
+/// method := "GET" | "POST" | "PUT" | "DELETE" | "HEAD"
+///         | "OPTIONS" | "CONNECT" | "PATCH" | "TRACE"
+///         | "ANY" | UPPER_CASE_IDENT
 /// ```
 ///
 #[proc_macro]
 pub fn define_routes(input: TokenStream) -> TokenStream {
-    (parse_macro_input!(input as Routes)).render().into()
+    let is_function = is_function_form(&input.clone().into());
+    match syn::parse::<Routes>(input).and_then(|routes| routes.render()) {
+        Ok(routes) => routes.into(),
+        Err(err) => {
+            // There may be more than one error, such as when reporting conflicting routes. When the
+            // macro is used in expression position, wrap the errors in a block.
+            let err = err.to_compile_error();
+            if is_function {
+                err.into()
+            } else {
+                quote! {
+                    { #err }
+                }
+                .into()
+            }
+        }
+    }
+}
+
+/// Define a function that returns routes, where the body of the function is a routing table.
+///
+/// This is equivalent to defining a function with [`define_routes!`], without having to wrap the
+/// function in a macro call. The signature, visibility and attributes of the function, including
+/// any doc comments, are kept, and the body is replaced with the routes. As the body of the function
+/// must be valid Rust, the routes are wrapped in `routes! { ... }`:
+///
+/// ```ignore
+/// /// The routes of the admin pages.
+/// #[routes]
+/// pub fn admin() -> Route {
+///     routes! {
+///         "/"         index   GET
+///         "/users"    users   GET POST
+///     }
+/// }
+/// ```
+///
+/// The routes use the same syntax as [`define_routes!`], always starting from `poem::Route::new()`.
+/// Any options are given as the arguments of the attribute, such as
+/// `#[routes(naming = suffix, paths = admin_paths)]`.
+#[proc_macro_attribute]
+pub fn routes(args: TokenStream, item: TokenStream) -> TokenStream {
+    let routes = syn::parse::Parser::parse(Options::parse_args, args).and_then(|options| {
+        syn::parse::Parser::parse(
+            |input: ParseStream| Routes::parse_item(input, options),
+            item,
+        )
+    });
+
+    match routes.and_then(|routes| routes.render()) {
+        Ok(routes) => routes.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// Expand an attribute for a specific method, reporting any error as a compile error.
+fn method_attribute(method: &str, args: TokenStream, item: TokenStream) -> TokenStream {
+    match handler::expand_method_attribute(method, args, item) {
+        Ok(handler) => handler.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// Declare a handler for `GET` requests to a path.
+///
+/// This applies Poem's `#[handler]` attribute to the function, and records the path and method so
+/// that the handler can be added to a route with [`collect_routes!`]:
+///
+/// ```ignore
+/// #[get("/pastes/:id")]
+/// async fn get_paste(Path(id): Path<u64>) -> String {
+///     format!("paste {id}")
+/// }
+///
+/// let app = collect_routes![get_paste, post_paste];
+/// ```
+///
+/// There is an attribute for each of the standard methods. Extension methods can be given with
+/// [`macro@route`].
+#[proc_macro_attribute]
+pub fn get(args: TokenStream, item: TokenStream) -> TokenStream {
+    method_attribute("GET", args, item)
+}
+
+/// Declare a handler for `POST` requests to a path. See [`macro@get`].
+#[proc_macro_attribute]
+pub fn post(args: TokenStream, item: TokenStream) -> TokenStream {
+    method_attribute("POST", args, item)
+}
+
+/// Declare a handler for `PUT` requests to a path. See [`macro@get`].
+#[proc_macro_attribute]
+pub fn put(args: TokenStream, item: TokenStream) -> TokenStream {
+    method_attribute("PUT", args, item)
+}
+
+/// Declare a handler for `DELETE` requests to a path. See [`macro@get`].
+#[proc_macro_attribute]
+pub fn delete(args: TokenStream, item: TokenStream) -> TokenStream {
+    method_attribute("DELETE", args, item)
+}
+
+/// Declare a handler for `HEAD` requests to a path. See [`macro@get`].
+#[proc_macro_attribute]
+pub fn head(args: TokenStream, item: TokenStream) -> TokenStream {
+    method_attribute("HEAD", args, item)
+}
+
+/// Declare a handler for `OPTIONS` requests to a path. See [`macro@get`].
+#[proc_macro_attribute]
+pub fn options(args: TokenStream, item: TokenStream) -> TokenStream {
+    method_attribute("OPTIONS", args, item)
+}
+
+/// Declare a handler for `CONNECT` requests to a path. See [`macro@get`].
+#[proc_macro_attribute]
+pub fn connect(args: TokenStream, item: TokenStream) -> TokenStream {
+    method_attribute("CONNECT", args, item)
+}
+
+/// Declare a handler for `PATCH` requests to a path. See [`macro@get`].
+#[proc_macro_attribute]
+pub fn patch(args: TokenStream, item: TokenStream) -> TokenStream {
+    method_attribute("PATCH", args, item)
+}
+
+/// Declare a handler for `TRACE` requests to a path. See [`macro@get`].
+#[proc_macro_attribute]
+pub fn trace(args: TokenStream, item: TokenStream) -> TokenStream {
+    method_attribute("TRACE", args, item)
+}
+
+/// Declare a handler for any method, given after the path, such as
+/// `#[route("/collections/:id", PROPFIND)]`. See [`macro@get`].
+///
+/// The method is parsed in the same way as in [`define_routes!`], except that `ANY` is not
+/// supported.
+#[proc_macro_attribute]
+pub fn route(args: TokenStream, item: TokenStream) -> TokenStream {
+    match handler::expand_route_attribute(args, item) {
+        Ok(handler) => handler.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// Build a Poem `Route` from handlers declared with [`macro@get`],
+/// [`macro@post`], [`macro@route`] and the like, such as `collect_routes![pastes::get_paste,
+/// pastes::post_paste]`.
+///
+/// Handlers with the same path are merged into a single method router, in the same way as the
+/// routes of [`define_routes!`].
+#[proc_macro]
+pub fn collect_routes(input: TokenStream) -> TokenStream {
+    match syn::parse::<handler::CollectRoutes>(input) {
+        Ok(routes) => routes.render().into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// Derive typed routes for an enum, where each variant has an `#[at("...")]` attribute giving the
+/// path of the route, and a field for each parameter in the path:
+///
+/// ```ignore
+/// #[derive(Routes)]
+/// enum AppRoute {
+///     #[at("/", GET)]
+///     Index,
+///     #[at("/pastes/:id", GET POST)]
+///     Paste { id: u64 },
+///     #[at("/files/*path")]
+///     File(String),
+/// }
+///
+/// assert_eq!(AppRoute::Paste { id: 42 }.to_string(), "/pastes/42");
+/// assert_eq!("/pastes/42".parse(), Ok(AppRoute::Paste { id: 42 }));
+/// let app = AppRoute::router();
+/// ```
+///
+/// This generates a `Display` implementation that builds the URL of a route, and a `FromStr`
+/// implementation that matches a URL against each of the routes in the order in which they are
+/// declared. Parameters are percent-encoded and decoded, and converted using their `Display` and
+/// `FromStr` implementations. These only use the standard library, so the enum can be used in a
+/// frontend that is compiled to WASM.
+///
+/// Any methods given after the path are routed by the generated `router` function, using handlers
+/// named after the variant in the same way as [`define_routes!`], such as `get_paste` and
+/// `post_paste`. Variants without methods are not routed. The `router` function is not generated
+/// when compiling to WASM, or when none of the variants have methods, in which case the enum does
+/// not depend on Poem.
+#[proc_macro_derive(Routes, attributes(at))]
+pub fn derive_routes(input: TokenStream) -> TokenStream {
+    match syn::parse::<syn::DeriveInput>(input).and_then(derive::derive_routes) {
+        Ok(routes) => routes.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// Check whether the input to the macro defines a function, in which case the macro is used in item
+/// position. This looks for a `fn` before the braces that surround the routes.
+fn is_function_form(input: &proc_macro2::TokenStream) -> bool {
+    for token in input.clone() {
+        match token {
+            proc_macro2::TokenTree::Ident(ident) if ident == "fn" => return true,
+            proc_macro2::TokenTree::Group(group)
+                if group.delimiter() == proc_macro2::Delimiter::Brace =>
+            {
+                return false
+            }
+            _ => {}
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse::Parser;
+
+    use super::*;
+
+    /// Parse and check the given routes, returning each of the error messages.
+    fn check(routes: &str) -> Vec<String> {
+        match parse_routes
+            .parse_str(routes)
+            .and_then(|routes| check_routes(&routes))
+        {
+            Ok(()) => Vec::new(),
+            Err(err) => err.into_iter().map(|err| err.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn peeks_function_signatures() {
+        let peek = |input: &str| {
+            let parser = |input: ParseStream| {
+                let peeked = RoutesFn::peek(input);
+                input.parse::<proc_macro2::TokenStream>()?;
+                Ok(peeked)
+            };
+
+            parser.parse_str(input).unwrap()
+        };
+
+        for input in [
+            "fn routes() -> Route {}",
+            "pub fn routes() -> Route {}",
+            "pub(crate) fn routes() -> Route {}",
+            "/// Docs.\n#[inline] pub fn routes() -> Route {}",
+            "async fn routes() -> Route {}",
+            "pub const fn routes() -> Route {}",
+            "unsafe fn routes() -> Route {}",
+            "pub(crate) const async unsafe extern \"C\" fn routes() -> Route {}",
+        ] {
+            assert!(peek(input), "`{input}` should be a function");
+        }
+
+        for input in [
+            "{ \"/\" index GET }",
+            "Route::new(), {}",
+            "unsafe { make_route() }, {}",
+            "async { route }, {}",
+        ] {
+            assert!(!peek(input), "`{input}` should not be a function");
+        }
+    }
+
+    #[test]
+    fn join_paths() {
+        assert_eq!(super::join_paths("", "/users"), "/users");
+        assert_eq!(super::join_paths("/admin", "/users"), "/admin/users");
+        assert_eq!(super::join_paths("/admin/", "/users"), "/admin/users");
+        assert_eq!(super::join_paths("/admin", "/"), "/admin");
+        assert_eq!(super::join_paths("", "/"), "/");
+    }
+
+    #[test]
+    fn accepts_distinct_routes() {
+        assert!(check(r#""/" index GET "/users" users GET POST "/users/:id" user GET"#).is_empty());
+        assert!(check(r#""/users/:id<\\d+>" user GET "/users/:name" named GET"#).is_empty());
+        assert!(check(r#""/a" a GET "/a" b POST"#).is_empty());
+        assert!(check(r#"* "/api" { api } "/" index GET"#).is_empty());
+    }
+
+    #[test]
+    fn reports_duplicate_routes() {
+        assert_eq!(
+            check(r#""/users" users GET "/" index GET "/users" other GET"#),
+            [
+                "duplicate method `GET` for route `/users`",
+                "method `GET` is first given here"
+            ]
+        );
+
+        assert_eq!(
+            check(r#"* "/api" { api } * "/api" { other }"#),
+            [
+                "duplicate route `/api`",
+                "route `/api` is first defined here"
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_conflicting_routes() {
+        assert_eq!(
+            check(r#""/users/:id" user GET "/users/:name" named POST"#),
+            [
+                "route `/users/:name` conflicts with route `/users/:id`",
+                "route `/users/:id` is defined here"
+            ]
+        );
+
+        assert_eq!(
+            check(r#""/files/*path" files GET "/files/*" all GET"#),
+            [
+                "route `/files/*` conflicts with route `/files/*path`",
+                "route `/files/*path` is defined here"
+            ]
+        );
+
+        // A nested route also adds a wildcard under its path.
+        assert_eq!(
+            check(r#""/api/*rest" rest GET * "/api" { api }"#),
+            [
+                "route `/api` conflicts with route `/api/*rest`",
+                "route `/api/*rest` is defined here"
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_conflicts_in_scoped_routes() {
+        assert_eq!(
+            check(r#""/users" users GET mod admin { "/users" list GET }"#),
+            [
+                "duplicate method `GET` for route `/users`",
+                "method `GET` is first given here"
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_conflicts_in_inline_nested_routes() {
+        assert_eq!(
+            check(r#"* "/api" routes { "/a" a GET "/a" b GET }"#),
+            [
+                "duplicate method `GET` for route `/a`",
+                "method `GET` is first given here"
+            ]
+        );
+
+        // Routes within a nested route are separate from the routes outside of it.
+        assert!(check(r#""/a" a GET * "/api" routes { "/a" a GET }"#).is_empty());
+    }
+
+    #[test]
+    fn reports_invalid_methods() {
+        assert_eq!(
+            check(r#""/a" a ANY "/a" b GET"#),
+            [
+                "the `ANY` method cannot be combined with other methods",
+                "other method given for `/a` here"
+            ]
+        );
+
+        assert_eq!(
+            check(r#""/a" a GET with A "/a" b POST with B"#),
+            [
+                "the `with`, `data` and `catch` clauses for `/a` can only be given on one of its \
+                 routes, as they apply to all of the methods of the path",
+                "clauses for `/a` are first given here"
+            ]
+        );
+    }
+
+    #[test]
+    fn merges_routes_with_the_same_path() {
+        let routes = parse_routes
+            .parse_str(r#""/a" a GET "/b" b GET "/a" c POST mod m { "/b" d PUT }"#)
+            .unwrap();
+
+        let mut entries = Vec::new();
+        collect_entries(&routes, &mut entries);
+
+        let entries = entries
+            .iter()
+            .map(|entry| match entry {
+                RouteEntry::Standard(routes) => routes
+                    .iter()
+                    .map(|route| route.path.lit.value())
+                    .collect::<Vec<_>>(),
+                RouteEntry::Nested(_) => panic!("unexpected nested route"),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(entries, [["/a", "/a"], ["/b", "/b"]]);
+    }
 }
@@ -0,0 +1,310 @@
+//! Checking the routes against a lock file.
+//!
+//! When the `lock` option is given, the routes are compared against a snapshot of the routes that
+//! is committed alongside the code, and any differences are reported as a compile error. This
+//! catches accidental changes to the paths or methods of the routes, which would break the URLs
+//! that clients rely on. Setting the `POEM_ROUTES_UPDATE` environment variable to `1` rewrites the
+//! lock file with the current routes instead.
+//!
+//! The lock file uses the same format as the JSON manifest, so that it can be reviewed in a diff.
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+use quote::quote;
+
+use crate::manifest::write_if_changed;
+
+/// The environment variable that causes the lock file to be rewritten.
+const UPDATE_VAR: &str = "POEM_ROUTES_UPDATE";
+
+/// Resolve the path of the lock file, relative to the directory containing the `Cargo.toml` of the
+/// crate.
+fn resolve_lock_path(path: &str) -> PathBuf {
+    match std::env::var_os("CARGO_MANIFEST_DIR") {
+        Some(dir) => PathBuf::from(dir).join(path),
+        None => PathBuf::from(path),
+    }
+}
+
+/// Whether the lock file should be rewritten rather than checked.
+fn should_update() -> bool {
+    std::env::var(UPDATE_VAR).is_ok_and(|value| value == "1")
+}
+
+/// Index the entries of a list in the lock file by their path.
+///
+/// A path can have more than one entry, such as in a lock file written before routes that share a
+/// path were merged, or when a nested route has a route with the same full path as a route outside
+/// of it, so all of the entries for a path are compared together.
+fn index<'a>(
+    value: &'a serde_json::Value,
+    key: &str,
+) -> Result<BTreeMap<&'a str, Vec<&'a serde_json::Value>>, String> {
+    let entries = match value.get(key) {
+        Some(serde_json::Value::Array(entries)) => entries,
+        Some(_) => return Err(format!("`{key}` must be a list")),
+        None => return Ok(BTreeMap::new()),
+    };
+
+    let mut index: BTreeMap<&str, Vec<&serde_json::Value>> = BTreeMap::new();
+    for entry in entries {
+        match entry.get("path").and_then(|path| path.as_str()) {
+            Some(path) => index.entry(path).or_default().push(entry),
+            None => return Err(format!("each entry in `{key}` must have a `path`")),
+        }
+    }
+
+    Ok(index)
+}
+
+/// Describe a standard route, such as `GET => get_user, POST => post_user`.
+fn describe_route(route: &serde_json::Value) -> String {
+    let strings = |key: &str| {
+        route
+            .get(key)
+            .and_then(|values| values.as_array())
+            .map(|values| {
+                values
+                    .iter()
+                    .map(|value| value.as_str().unwrap_or_default())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default()
+    };
+
+    strings("methods")
+        .into_iter()
+        .zip(strings("handlers"))
+        .map(|(method, handler)| format!("{method} => {handler}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Describe a nested route, such as `strip, inline`.
+fn describe_nested(nested: &serde_json::Value) -> String {
+    let flag = |key: &str| nested.get(key).and_then(|value| value.as_bool());
+    let strip = if flag("strip") == Some(false) {
+        "no strip"
+    } else {
+        "strip"
+    };
+
+    let inline = if flag("inline") == Some(true) {
+        "inline"
+    } else {
+        "endpoint"
+    };
+
+    format!("{strip}, {inline}")
+}
+
+/// Compare the entries of one list in the lock file with the current entries, describing each
+/// route that has been added, removed or changed.
+fn diff_entries(
+    locked: &serde_json::Value,
+    current: &serde_json::Value,
+    key: &str,
+    what: &str,
+    describe: fn(&serde_json::Value) -> String,
+) -> Result<Vec<String>, String> {
+    let locked = index(locked, key)?;
+    let current = index(current, key)?;
+    let describe = |entries: &Vec<&serde_json::Value>| {
+        entries
+            .iter()
+            .map(|entry| describe(entry))
+            .collect::<Vec<_>>()
+            .join("; ")
+    };
+
+    let mut changes = Vec::new();
+
+    for (path, entries) in &current {
+        match locked.get(path) {
+            None => changes.push(format!("added {what} `{path}` ({})", describe(entries))),
+            Some(old) if old != entries => changes.push(format!(
+                "changed {what} `{path}` from ({}) to ({})",
+                describe(old),
+                describe(entries)
+            )),
+            Some(_) => {}
+        }
+    }
+
+    for (path, entries) in &locked {
+        if !current.contains_key(path) {
+            changes.push(format!("removed {what} `{path}` ({})", describe(entries)));
+        }
+    }
+
+    Ok(changes)
+}
+
+/// Compare the contents of the lock file with the current routes, describing each difference.
+fn diff(locked: &str, current: &str) -> Result<Vec<String>, String> {
+    let locked: serde_json::Value = serde_json::from_str(locked).map_err(|err| err.to_string())?;
+    let current: serde_json::Value =
+        serde_json::from_str(current).expect("the rendered routes are valid JSON");
+
+    let mut changes = diff_entries(&locked, &current, "routes", "route", describe_route)?;
+    changes.extend(diff_entries(
+        &locked,
+        &current,
+        "nested",
+        "nested route",
+        describe_nested,
+    )?);
+
+    Ok(changes)
+}
+
+/// Check the routes against the lock file given by the `lock` option, or rewrite the lock file if
+/// the `POEM_ROUTES_UPDATE` environment variable is set to `1`.
+///
+/// On success, this returns tokens that make Cargo rebuild the crate when the lock file or the
+/// environment variable changes.
+pub(crate) fn check_lock(path: &syn::LitStr, json: &str) -> syn::Result<proc_macro2::TokenStream> {
+    let target = resolve_lock_path(&path.value());
+    let error = |message: String| syn::Error::new(path.span(), message);
+
+    if should_update() {
+        write_if_changed(&target, json).map_err(|err| {
+            error(format!(
+                "failed to write route lock file `{}`: {err}",
+                target.display()
+            ))
+        })?;
+    } else {
+        let locked = std::fs::read_to_string(&target).map_err(|err| {
+            error(format!(
+                "failed to read route lock file `{}`: {err}; set {UPDATE_VAR}=1 to create it",
+                target.display()
+            ))
+        })?;
+
+        let changes = diff(&locked, json).map_err(|err| {
+            error(format!(
+                "invalid route lock file `{}`: {err}",
+                target.display()
+            ))
+        })?;
+
+        if !changes.is_empty() {
+            return Err(error(format!(
+                "the routes do not match the lock file `{}`; set {UPDATE_VAR}=1 to update it:\n  {}",
+                target.display(),
+                changes.join("\n  ")
+            )));
+        }
+    }
+
+    let target = target.to_string_lossy();
+    Ok(quote! {
+        const _: &[u8] = ::core::include_bytes!(#target);
+        const _: ::core::option::Option<&str> = ::core::option_env!(#UPDATE_VAR);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::{render_json, RouteRecord};
+
+    /// Render the JSON for the given routes, each of which is a path and a list of methods.
+    fn render(routes: &[(&str, &[&str])]) -> String {
+        let routes = routes
+            .iter()
+            .map(|(path, methods)| RouteRecord {
+                path: path.to_string(),
+                prefix: String::new(),
+                methods: methods.iter().map(ToString::to_string).collect(),
+                handlers: methods
+                    .iter()
+                    .map(|method| format!("{}_handler", method.to_lowercase()))
+                    .collect(),
+            })
+            .collect::<Vec<_>>();
+
+        render_json(&routes, &[])
+    }
+
+    #[test]
+    fn accepts_unchanged_routes() {
+        let json = render(&[("/a", &["GET"]), ("/b", &["GET"]), ("/a", &["POST"])]);
+        assert_eq!(diff(&json, &json), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn reports_added_and_removed_routes() {
+        let locked = render(&[("/a", &["GET"]), ("/b", &["GET"])]);
+        let current = render(&[("/a", &["GET"]), ("/c", &["GET"])]);
+        assert_eq!(
+            diff(&locked, &current),
+            Ok(vec![
+                "added route `/c` (GET => get_handler)".to_string(),
+                "removed route `/b` (GET => get_handler)".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn reports_changes_to_routes_with_the_same_path() {
+        let locked = render(&[("/a", &["GET"]), ("/a", &["POST"])]);
+
+        // Change the method of the first of the two routes.
+        let current = render(&[("/a", &["DELETE"]), ("/a", &["POST"])]);
+        assert_eq!(
+            diff(&locked, &current),
+            Ok(vec![
+                "changed route `/a` from (GET => get_handler, POST => post_handler) to \
+                 (DELETE => delete_handler, POST => post_handler)"
+                    .to_string()
+            ])
+        );
+
+        // Change the method of the second of the two routes.
+        let current = render(&[("/a", &["GET"]), ("/a", &["PUT"])]);
+        assert_eq!(diff(&locked, &current).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn compares_all_entries_for_a_path() {
+        // A lock file can have more than one entry for the same path.
+        let locked = r#"{
+            "routes": [
+                { "path": "/a", "prefix": "", "methods": ["GET"], "handlers": ["get_a"] },
+                { "path": "/a", "prefix": "", "methods": ["POST"], "handlers": ["post_a"] }
+            ]
+        }"#;
+
+        let current = r#"{
+            "routes": [
+                { "path": "/a", "prefix": "", "methods": ["DELETE"], "handlers": ["delete_a"] },
+                { "path": "/a", "prefix": "", "methods": ["POST"], "handlers": ["post_a"] }
+            ]
+        }"#;
+
+        assert_eq!(
+            diff(locked, current),
+            Ok(vec![
+                "changed route `/a` from (GET => get_a; POST => post_a) to \
+                 (DELETE => delete_a; POST => post_a)"
+                    .to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn reports_invalid_lock_files() {
+        let current = render(&[("/a", &["GET"])]);
+        assert!(diff("not json", &current).is_err());
+        assert_eq!(
+            diff(r#"{ "routes": {} }"#, &current),
+            Err("`routes` must be a list".to_string())
+        );
+        assert_eq!(
+            diff(r#"{ "routes": [{}] }"#, &current),
+            Err("each entry in `routes` must have a `path`".to_string())
+        );
+    }
+}
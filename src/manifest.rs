@@ -0,0 +1,309 @@
+//! Generation of a manifest of the routes.
+
+use std::path::{Path, PathBuf};
+
+use quote::quote;
+use serde_json::json;
+
+use crate::{join_paths, FlatRoute, Naming, NestedEndpoint, Route};
+
+/// The environment variable that gives the directory in which relative `json` paths are written.
+const JSON_DIR_VAR: &str = "POEM_ROUTES_DIR";
+
+/// A description of a standard route, as it is included in the manifest.
+pub(crate) struct RouteRecord {
+    /// The full path of the route, including the paths of any enclosing nested routes.
+    pub(crate) path: String,
+    /// The paths of any enclosing nested routes.
+    pub(crate) prefix: String,
+    /// The methods of the route, in upper-case.
+    pub(crate) methods: Vec<String>,
+    /// The handler for each method.
+    pub(crate) handlers: Vec<String>,
+}
+
+/// Convert a path into a string, without the spaces that would be added by `ToTokens`.
+fn path_string(path: &syn::Path) -> String {
+    let segments = path
+        .segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect::<Vec<_>>()
+        .join("::");
+
+    if path.leading_colon.is_some() {
+        format!("::{segments}")
+    } else {
+        segments
+    }
+}
+
+impl RouteRecord {
+    pub(crate) fn new(route: &FlatRoute, naming: Naming) -> Self {
+        let (methods, handlers) = route
+            .route
+            .handlers
+            .resolve(naming)
+            .into_iter()
+            .map(|(entry, handler)| (entry.method.render().to_uppercase(), path_string(&handler)))
+            .unzip();
+
+        Self {
+            path: route.path.clone(),
+            prefix: route.prefix.clone(),
+            methods,
+            handlers,
+        }
+    }
+}
+
+/// A description of a nested route, as it is included in the JSON manifest.
+pub(crate) struct NestedRecord {
+    /// The full path of the nested route, including the paths of any enclosing nested routes.
+    pub(crate) path: String,
+    /// Whether the prefix is stripped from the path before it is passed to the nested endpoint.
+    pub(crate) strip: bool,
+    /// Whether the routes of the nested route are given inline, and so are included in the
+    /// manifest.
+    pub(crate) inline: bool,
+}
+
+/// Collect a description of each of the nested routes, including those within other nested routes.
+pub(crate) fn collect_nested(routes: &[Route], prefix: &str, nested: &mut Vec<NestedRecord>) {
+    for route in routes {
+        match route {
+            Route::Nested(route) => {
+                let path = join_paths(prefix, &route.path.lit.value());
+                let inline = matches!(route.endpoint, NestedEndpoint::Routes(_));
+                nested.push(NestedRecord {
+                    path: path.clone(),
+                    strip: !route.no_strip,
+                    inline,
+                });
+
+                if let NestedEndpoint::Routes(routes) = &route.endpoint {
+                    collect_nested(routes, &path, nested);
+                }
+            }
+
+            Route::Scoped(scoped) => collect_nested(&scoped.routes, prefix, nested),
+            Route::Standard(_) => {}
+        }
+    }
+}
+
+/// Render the routes as JSON.
+///
+/// Routes that share a path are registered with Poem as a single method router, so they are merged
+/// into one entry, in the position of the first of them, with the methods and handlers of each of
+/// them. The line numbers of the routes are not included, so that the JSON only changes when the
+/// routes themselves change.
+pub(crate) fn render_json(routes: &[RouteRecord], nested: &[NestedRecord]) -> String {
+    let mut merged: Vec<(&RouteRecord, Vec<&str>, Vec<&str>)> = Vec::new();
+    for route in routes {
+        let index = match merged
+            .iter()
+            .position(|(first, _, _)| first.path == route.path && first.prefix == route.prefix)
+        {
+            Some(index) => index,
+            None => {
+                merged.push((route, Vec::new(), Vec::new()));
+                merged.len() - 1
+            }
+        };
+
+        let (_, methods, handlers) = &mut merged[index];
+        methods.extend(route.methods.iter().map(String::as_str));
+        handlers.extend(route.handlers.iter().map(String::as_str));
+    }
+
+    let routes = merged
+        .iter()
+        .map(|(route, methods, handlers)| {
+            json!({
+                "path": route.path,
+                "prefix": route.prefix,
+                "methods": methods,
+                "handlers": handlers,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let nested = nested
+        .iter()
+        .map(|nested| {
+            json!({
+                "path": nested.path,
+                "strip": nested.strip,
+                "inline": nested.inline,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut json = serde_json::to_string_pretty(&json!({
+        "routes": routes,
+        "nested": nested,
+    }))
+    .expect("JSON values can always be serialized");
+
+    json.push('\n');
+    json
+}
+
+/// Resolve the path to which the JSON manifest is written.
+///
+/// Relative paths are resolved against the directory in the `POEM_ROUTES_DIR` environment
+/// variable, or the `OUT_DIR` of the crate if it has a build script, or else the directory
+/// containing the `Cargo.toml` of the crate.
+fn resolve_json_path(path: &str) -> PathBuf {
+    let path = PathBuf::from(path);
+    if path.is_absolute() {
+        return path;
+    }
+
+    [JSON_DIR_VAR, "OUT_DIR", "CARGO_MANIFEST_DIR"]
+        .into_iter()
+        .find_map(std::env::var_os)
+        .map(|dir| PathBuf::from(dir).join(&path))
+        .unwrap_or(path)
+}
+
+/// Write a file, creating any missing directories.
+///
+/// The file is only written if its contents have changed, so that an unchanged file does not cause
+/// anything that depends on it to be rebuilt.
+pub(crate) fn write_if_changed(target: &Path, contents: &str) -> std::io::Result<()> {
+    if std::fs::read_to_string(target).is_ok_and(|existing| existing == contents) {
+        return Ok(());
+    }
+
+    if let Some(parent) = target.parent() {
+        if !parent.as_os_str().is_empty() {
+            std::fs::create_dir_all(parent)?;
+        }
+    }
+
+    std::fs::write(target, contents)
+}
+
+/// Write the JSON manifest to the file given by the `json` option.
+pub(crate) fn write_json(path: &syn::LitStr, json: &str) -> syn::Result<()> {
+    let target = resolve_json_path(&path.value());
+    write_if_changed(&target, json).map_err(|err| {
+        syn::Error::new(
+            path.span(),
+            format!(
+                "failed to write route manifest to `{}`: {err}",
+                target.display()
+            ),
+        )
+    })
+}
+
+/// Render the module containing the manifest of the given routes.
+pub(crate) fn render_manifest(
+    vis: &syn::Visibility,
+    module: &syn::Ident,
+    routes: &[FlatRoute],
+    naming: Naming,
+) -> proc_macro2::TokenStream {
+    let routes = routes.iter().map(|route| {
+        let line = route.route.path.span().start().line as u32;
+        let RouteRecord {
+            path,
+            prefix,
+            methods,
+            handlers,
+        } = RouteRecord::new(route, naming);
+
+        quote! {
+            RouteInfo {
+                path: #path,
+                prefix: #prefix,
+                methods: &[#(#methods),*],
+                handlers: &[#(#handlers),*],
+                line: #line,
+            }
+        }
+    });
+
+    quote! {
+        #[allow(dead_code)]
+        #vis mod #module {
+            /// Information about a route.
+            #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+            pub struct RouteInfo {
+                /// The full path of the route, including the paths of any enclosing nested routes.
+                pub path: &'static str,
+                /// The paths of any enclosing nested routes, or an empty string.
+                pub prefix: &'static str,
+                /// The methods of the route, in upper-case.
+                pub methods: &'static [&'static str],
+                /// The path of the handler for each of the methods.
+                pub handlers: &'static [&'static str],
+                /// The line in the source file on which the route is defined, or zero if the
+                /// compiler is older than Rust 1.88.
+                pub line: u32,
+            }
+
+            /// The routes, in the order in which they are defined.
+            pub static ROUTES: &[RouteInfo] = &[#(#routes),*];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(path: &str, prefix: &str, methods: &[&str], handlers: &[&str]) -> RouteRecord {
+        RouteRecord {
+            path: path.to_string(),
+            prefix: prefix.to_string(),
+            methods: methods.iter().map(ToString::to_string).collect(),
+            handlers: handlers.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    #[test]
+    fn merges_routes_with_the_same_path() {
+        let routes = [
+            record("/a", "", &["GET"], &["get_a"]),
+            record("/b", "", &["GET"], &["get_b"]),
+            record("/a", "", &["POST", "PUT"], &["post_a", "put_a"]),
+            record("/api/a", "", &["GET"], &["get_api_a"]),
+            record("/api/a", "/api", &["GET"], &["nested::get_a"]),
+        ];
+
+        let json: serde_json::Value = serde_json::from_str(&render_json(&routes, &[])).unwrap();
+        assert_eq!(
+            json["routes"],
+            json!([
+                {
+                    "path": "/a",
+                    "prefix": "",
+                    "methods": ["GET", "POST", "PUT"],
+                    "handlers": ["get_a", "post_a", "put_a"],
+                },
+                {
+                    "path": "/b",
+                    "prefix": "",
+                    "methods": ["GET"],
+                    "handlers": ["get_b"],
+                },
+                {
+                    "path": "/api/a",
+                    "prefix": "",
+                    "methods": ["GET"],
+                    "handlers": ["get_api_a"],
+                },
+                {
+                    "path": "/api/a",
+                    "prefix": "/api",
+                    "methods": ["GET"],
+                    "handlers": ["nested::get_a"],
+                },
+            ])
+        );
+    }
+}
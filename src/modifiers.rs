@@ -0,0 +1,381 @@
+//! Modifiers that wrap the endpoint of a route.
+//!
+//! A route can be followed by a `with` clause listing middleware, such as
+//! `"/upload" upload POST with SizeLimit::new(1 << 20), Tracing`, and by `data(...)`, which adds
+//! data to each request, such as `data(config.clone())`. Errors of a specific type can be turned
+//! into a response with a `catch` clause, such as `catch NotFoundError => not_found_page`. The
+//! modifiers are applied to the endpoint of the route in the order in which they are given, so the
+//! last is the outermost.
+//!
+//! The modifiers given after all of the routes can also include a `fallback` clause, such as
+//! `fallback => error_page`, which turns any error into a response.
+
+use quote::quote;
+use syn::{
+    parenthesized,
+    parse::{Parse, ParseStream},
+};
+
+use crate::keyword;
+
+/// A single modifier of an endpoint.
+enum Modifier {
+    /// Middleware, given in a `with` clause.
+    With(syn::Expr),
+    /// Data that is added to each request, given with `data(...)`.
+    Data(syn::Expr),
+    /// A handler for a specific type of error, given in a `catch` clause.
+    Catch(Box<syn::Type>, syn::Expr),
+    /// A handler for any error, given in a `fallback` clause.
+    Fallback(syn::Expr),
+}
+
+/// The modifiers given after a route.
+#[derive(Default)]
+pub(crate) struct Modifiers {
+    /// The modifiers, in the order in which they are applied.
+    modifiers: Vec<Modifier>,
+}
+
+impl Modifiers {
+    /// Whether the input starts with a modifier.
+    pub(crate) fn peek(input: ParseStream) -> bool {
+        input.peek(keyword::with) || input.peek(keyword::data) || input.peek(keyword::catch)
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.modifiers.is_empty()
+    }
+
+    /// Parse any modifiers, stopping at a brace rather than treating it as a struct literal if
+    /// `stop_at_brace` is set.
+    pub(crate) fn parse_until(input: ParseStream, stop_at_brace: bool) -> syn::Result<Self> {
+        let mut modifiers = Self::default();
+
+        while Self::peek(input) {
+            modifiers.parse_modifier(input, stop_at_brace)?;
+        }
+
+        Ok(modifiers)
+    }
+
+    /// Parse the modifiers given after all of the routes, which can also include a `fallback`.
+    pub(crate) fn parse_top_level(input: ParseStream) -> syn::Result<Self> {
+        let mut modifiers = Self::default();
+
+        loop {
+            if input.peek(keyword::fallback) {
+                input.parse::<keyword::fallback>()?;
+                input.parse::<syn::Token![=>]>()?;
+                let handler = parse_expr(input, false)?;
+                modifiers.modifiers.push(Modifier::Fallback(handler));
+            } else if Self::peek(input) {
+                modifiers.parse_modifier(input, false)?;
+            } else {
+                break;
+            }
+        }
+
+        Ok(modifiers)
+    }
+
+    /// Parse a single `with`, `data` or `catch` clause.
+    fn parse_modifier(&mut self, input: ParseStream, stop_at_brace: bool) -> syn::Result<()> {
+        let lookahead = input.lookahead1();
+        if lookahead.peek(keyword::data) {
+            input.parse::<keyword::data>()?;
+
+            let content;
+            parenthesized!(content in input);
+            self.modifiers.push(Modifier::Data(content.parse()?));
+        } else if lookahead.peek(keyword::catch) {
+            input.parse::<keyword::catch>()?;
+            let error = input.parse()?;
+            input.parse::<syn::Token![=>]>()?;
+            let handler = parse_expr(input, stop_at_brace)?;
+            self.modifiers.push(Modifier::Catch(error, handler));
+        } else if lookahead.peek(keyword::with) {
+            input.parse::<keyword::with>()?;
+            loop {
+                self.modifiers
+                    .push(Modifier::With(parse_expr(input, stop_at_brace)?));
+
+                if input.parse::<Option<syn::Token![,]>>()?.is_none() {
+                    break;
+                }
+            }
+        } else {
+            return Err(lookahead.error());
+        }
+
+        Ok(())
+    }
+
+    /// Apply the modifiers to an endpoint.
+    pub(crate) fn apply(&self, endpoint: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+        self.modifiers
+            .iter()
+            .fold(endpoint, |endpoint, modifier| match modifier {
+                Modifier::With(middleware) => quote! {
+                    poem::EndpointExt::with(#endpoint, #middleware)
+                },
+                Modifier::Data(data) => quote! {
+                    poem::EndpointExt::data(#endpoint, #data)
+                },
+                // The error type is given explicitly, so that the handler can be a closure.
+                Modifier::Catch(error, handler) => quote! {
+                    poem::EndpointExt::catch_error::<_, _, _, #error>(#endpoint, #handler)
+                },
+                Modifier::Fallback(handler) => quote! {
+                    poem::EndpointExt::catch_all_error(#endpoint, #handler)
+                },
+            })
+    }
+}
+
+impl Parse for Modifiers {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        Self::parse_until(input, false)
+    }
+}
+
+/// The keywords that need something after them, such as the block in `|_| async { ... }`.
+const PREFIX_KEYWORDS: &[&str] = &["async", "move", "unsafe", "if", "match"];
+
+/// The keywords that continue a complete term, such as in `limit as u64`.
+const INFIX_KEYWORDS: &[&str] = &["as", "else"];
+
+/// Parse an expression in a modifier.
+///
+/// Routes are not separated by any punctuation, so parsing a `syn::Expr` would run on into the next
+/// route, such as by taking the `*` of a nested route as a multiplication. Instead, the expression is
+/// taken up to a comma, or up to an identifier, literal, `*` or brace that follows a complete term
+/// and so starts something else. This means that a comma outside of any brackets, a multiplication,
+/// and a brace after a complete term when `stop_at_brace` is set all end the expression, unless it
+/// is wrapped in parentheses.
+fn parse_expr(input: ParseStream, stop_at_brace: bool) -> syn::Result<syn::Expr> {
+    let tokens = input.step(|cursor| {
+        let mut rest = *cursor;
+        let mut tokens = proc_macro2::TokenStream::new();
+        let mut complete = false;
+
+        while let Some((token, next)) = rest.token_tree() {
+            let stop = match &token {
+                proc_macro2::TokenTree::Punct(punct) => {
+                    punct.as_char() == ',' || (complete && punct.as_char() == '*')
+                }
+
+                proc_macro2::TokenTree::Group(group) => {
+                    complete && stop_at_brace && group.delimiter() == proc_macro2::Delimiter::Brace
+                }
+
+                proc_macro2::TokenTree::Ident(ident) => {
+                    complete && !INFIX_KEYWORDS.iter().any(|keyword| ident == keyword)
+                }
+
+                proc_macro2::TokenTree::Literal(_) => complete,
+            };
+
+            if stop {
+                break;
+            }
+
+            // A `?` continues a complete term, and any other punctuation needs something after it,
+            // as do the keywords that come before or between parts of an expression.
+            complete = match &token {
+                proc_macro2::TokenTree::Punct(punct) => complete && punct.as_char() == '?',
+                proc_macro2::TokenTree::Ident(ident) => !PREFIX_KEYWORDS
+                    .iter()
+                    .chain(INFIX_KEYWORDS)
+                    .any(|keyword| ident == keyword),
+                _ => true,
+            };
+
+            tokens.extend([token]);
+            rest = next;
+        }
+
+        Ok((tokens, rest))
+    })?;
+
+    if tokens.is_empty() {
+        return Err(input.error("expected an expression"));
+    }
+
+    syn::parse2(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse::Parser;
+
+    use super::*;
+
+    /// Parse an expression from the start of the input, returning the expression and the rest of
+    /// the input.
+    fn split(input: &str, stop_at_brace: bool) -> (String, String) {
+        let parser = |input: ParseStream| {
+            let expr = parse_expr(input, stop_at_brace)?;
+            let rest = input.parse::<proc_macro2::TokenStream>()?;
+            Ok((quote!(#expr).to_string(), rest.to_string()))
+        };
+
+        parser.parse_str(input).unwrap()
+    }
+
+    /// Parse an expression that should take all of the input.
+    fn whole(input: &str) -> String {
+        let (expr, rest) = split(input, false);
+        assert_eq!(rest, "", "`{input}` was not parsed as a whole");
+        expr
+    }
+
+    #[test]
+    fn parses_middleware() {
+        for input in [
+            "Tracing",
+            "SizeLimit::new(1 << 20)",
+            "Cors::new().allow_origin(\"https://example.com\").allow_credentials(true)",
+            "poem::middleware::Compression::new()",
+            "AddData::new(pool.clone())",
+            "make_middleware(&config)?",
+            "Limit::<1024>::new()",
+            "vec![a, b].into_iter().collect::<Vec<_>>()",
+            "Config { retries: 3 }",
+            "(a * b)",
+            "{ if enabled { a } else { b } }",
+            "(match mode { Mode::A => a, Mode::B => b })",
+            "(if enabled { a } else { b })",
+        ] {
+            let expr = syn::parse_str::<syn::Expr>(input).unwrap();
+            assert_eq!(whole(input), quote!(#expr).to_string());
+        }
+    }
+
+    #[test]
+    fn parses_closures() {
+        for input in [
+            "not_found_page",
+            "admin::not_found_page",
+            "|_| async { \"too large\" }",
+            "|_| async move { StatusCode::NOT_FOUND }",
+            "move |err: NotFoundError| async move { err.to_string() }",
+            "|err| handle(err)",
+            "|_: SizedLimitError| { \"too large\" }",
+            "|_| unsafe { make_response() }",
+        ] {
+            let (expr, rest) = split(input, true);
+            assert_eq!(rest, "", "`{input}` was not parsed as a whole");
+            assert_eq!(expr, whole(input));
+        }
+    }
+
+    #[test]
+    fn parses_keywords() {
+        for input in [
+            "limit as u64",
+            "if enabled { a } else { b }",
+            "if a { x } else if b { y } else { z }",
+            "match mode { Mode::A => a, _ => b }",
+        ] {
+            let expr = syn::parse_str::<syn::Expr>(input).unwrap();
+            assert_eq!(whole(input), quote!(#expr).to_string());
+        }
+    }
+
+    #[test]
+    fn splits_expressions_that_need_parentheses() {
+        let parse = |input: &str, stop_at_brace: bool| {
+            let parser = |input: ParseStream| parse_expr(input, stop_at_brace);
+            parser.parse_str(input).map(|_| ())
+        };
+
+        // A comma outside of any brackets ends the expression.
+        assert!(parse("Limit::<1, 2>::new()", false).is_err());
+        assert!(parse("(Limit::<1, 2>::new())", false).is_ok());
+
+        // As does a `*`, which could be the start of a nested route.
+        assert_eq!(split("a * b", false).1, "* b");
+        assert_eq!(split("(a * b)", false).1, "");
+
+        // Before the routes of a group, a brace after a complete term ends the expression.
+        assert!(parse("if enabled { a } else { b }", true).is_err());
+        assert!(parse("(if enabled { a } else { b })", true).is_ok());
+    }
+
+    #[test]
+    fn stops_at_the_next_route() {
+        assert_eq!(
+            split("A, B \"/next\" next GET", false),
+            ("A".to_string(), ", B \"/next\" next GET".to_string())
+        );
+        assert_eq!(
+            split("Tracing * \"/api\" { api }", false),
+            ("Tracing".to_string(), "* \"/api\" { api }".to_string())
+        );
+        assert_eq!(
+            split("Compression::new() mod admin { }", false),
+            (
+                "Compression :: new ()".to_string(),
+                "mod admin { }".to_string()
+            )
+        );
+        assert_eq!(
+            split("|_| async { \"x\" } group \"/a\" { }", false),
+            (
+                "| _ | async { \"x\" }".to_string(),
+                "group \"/a\" { }".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn stops_at_the_routes_of_a_group() {
+        assert_eq!(
+            split("AdminOnly { \"/users\" users GET }", true),
+            (
+                "AdminOnly".to_string(),
+                "{ \"/users\" users GET }".to_string()
+            )
+        );
+        assert_eq!(
+            split("|_| async { \"x\" } { \"/users\" users GET }", true),
+            (
+                "| _ | async { \"x\" }".to_string(),
+                "{ \"/users\" users GET }".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn parses_modifiers_in_order() {
+        let parser = |input: ParseStream| {
+            let modifiers = Modifiers::parse_top_level(input)?;
+            Ok(modifiers
+                .apply(quote!(endpoint))
+                .to_string()
+                .replace(' ', ""))
+        };
+
+        let applied = parser
+            .parse_str(
+                "with A, B::new(1) data(pool.clone()) catch NotFound => |_| async { \"404\" } \
+                 fallback => error_page",
+            )
+            .unwrap();
+
+        assert_eq!(
+            applied,
+            "poem::EndpointExt::catch_all_error(\
+                poem::EndpointExt::catch_error::<_,_,_,NotFound>(\
+                    poem::EndpointExt::data(\
+                        poem::EndpointExt::with(\
+                            poem::EndpointExt::with(endpoint,A),\
+                        B::new(1)),\
+                    pool.clone()),\
+                |_|async{\"404\"}),\
+            error_page)"
+                .replace(' ', "")
+        );
+    }
+}
@@ -0,0 +1,333 @@
+//! Parsing of route paths.
+//!
+//! Route paths are parsed when the macro is expanded, so that a malformed path is reported as a
+//! compile error rather than as a panic when Poem builds the route tree. The syntax follows that
+//! of Poem:
+//!
+//! - static text, such as `/pastes`,
+//! - named parameters, such as `:id`,
+//! - named parameters constrained by a regular expression, such as `:id<\d+>`,
+//! - unnamed regular expressions, such as `<\d+>`, and
+//! - a wildcard at the end of the path, such as `*path`, or just `*`.
+//!
+//! Any regular expressions are compiled in the same way as Poem, so an invalid expression is also
+//! reported as a compile error.
+
+use std::fmt;
+
+use quote::ToTokens;
+use syn::parse::{Parse, ParseStream};
+
+/// A segment of a route path.
+pub(crate) enum Segment {
+    /// Static text that must match exactly.
+    Static(String),
+    /// A named parameter, such as `:id`.
+    Param(String),
+    /// A regular expression, with an optional name, such as `:id<\d+>`.
+    Regex(Option<String>, String),
+    /// A wildcard that matches the rest of the path, with an optional name, such as `*path`.
+    CatchAll(Option<String>),
+}
+
+impl Segment {
+    /// The name of the parameter captured by this segment, if any.
+    pub(crate) fn name(&self) -> Option<&str> {
+        match self {
+            Self::Static(_) => None,
+            Self::Param(name) => Some(name),
+            Self::Regex(name, _) | Self::CatchAll(name) => name.as_deref(),
+        }
+    }
+}
+
+impl fmt::Display for Segment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Static(text) => f.write_str(text),
+            Self::Param(name) => write!(f, ":{name}"),
+            Self::Regex(Some(name), regex) => write!(f, ":{name}<{regex}>"),
+            Self::Regex(None, regex) => write!(f, "<{regex}>"),
+            Self::CatchAll(Some(name)) => write!(f, "*{name}"),
+            Self::CatchAll(None) => f.write_str("*"),
+        }
+    }
+}
+
+/// A route path string that has been parsed into segments.
+pub(crate) struct RoutePath {
+    pub(crate) lit: syn::LitStr,
+    pub(crate) segments: Vec<Segment>,
+}
+
+impl Parse for RoutePath {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let lit: syn::LitStr = input.parse()?;
+        let segments = parse_segments(&lit.value())
+            .map_err(|err| syn::Error::new(lit.span(), format!("invalid route path: {err}")))?;
+        Ok(Self { lit, segments })
+    }
+}
+
+impl ToTokens for RoutePath {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        self.lit.to_tokens(tokens)
+    }
+}
+
+impl RoutePath {
+    pub(crate) fn span(&self) -> proc_macro2::Span {
+        self.lit.span()
+    }
+
+    /// The structure of the path, ignoring the names of any parameters.
+    ///
+    /// Two paths with the same structure will match the same requests, such as `/:id` and
+    /// `/:name`, so they cannot be told apart by the router.
+    pub(crate) fn pattern(&self) -> String {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Static(text) => text.clone(),
+                Segment::Param(_) => ":".to_string(),
+                Segment::Regex(_, regex) => format!("<{regex}>"),
+                Segment::CatchAll(_) => "*".to_string(),
+            })
+            .collect()
+    }
+
+    /// Whether the path is only static text, without any parameters or wildcards.
+    pub(crate) fn is_static(&self) -> bool {
+        self.segments
+            .iter()
+            .all(|segment| matches!(segment, Segment::Static(_)))
+    }
+}
+
+/// Take a name from the start of the path, up to the next delimiter.
+fn take_name(path: &str) -> (&str, &str) {
+    let end = path.find(['/', '<', '*']).unwrap_or(path.len());
+    path.split_at(end)
+}
+
+/// Take a regular expression from the start of the path, which is expected to have had the opening
+/// `<` removed, returning the expression and the remainder of the path after the closing `>`. The
+/// full path is only used for error messages.
+fn take_regex<'a>(full: &str, path: &'a str) -> Result<(&'a str, &'a str), String> {
+    let Some(end) = path.find('>') else {
+        return Err(format!("missing `>` after regular expression in `{full}`"));
+    };
+
+    let regex = &path[..end];
+    if regex.is_empty() {
+        return Err(format!("empty regular expression in `{full}`"));
+    }
+
+    Ok((regex, &path[end + 1..]))
+}
+
+pub(crate) fn parse_segments(path: &str) -> Result<Vec<Segment>, String> {
+    if !path.starts_with('/') {
+        return Err(format!("`{path}` must start with a `/`"));
+    }
+
+    let mut rest = path;
+    let mut segments = Vec::new();
+
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix(':') {
+            let (name, after) = take_name(after);
+            if name.is_empty() {
+                return Err(format!("missing parameter name after `:` in `{path}`"));
+            }
+
+            if let Some(after) = after.strip_prefix('<') {
+                let (regex, after) = take_regex(path, after)?;
+                segments.push(Segment::Regex(Some(name.to_string()), regex.to_string()));
+                rest = after;
+            } else {
+                segments.push(Segment::Param(name.to_string()));
+                rest = after;
+            }
+        } else if let Some(after) = rest.strip_prefix('<') {
+            let (regex, after) = take_regex(path, after)?;
+            segments.push(Segment::Regex(None, regex.to_string()));
+            rest = after;
+        } else if let Some(name) = rest.strip_prefix('*') {
+            if name.contains('/') {
+                return Err(format!("wildcard must be at the end of `{path}`"));
+            }
+
+            segments.push(Segment::CatchAll(if name.is_empty() {
+                None
+            } else {
+                Some(name.to_string())
+            }));
+
+            rest = "";
+        } else {
+            let end = rest.find([':', '<', '*']).unwrap_or(rest.len());
+            segments.push(Segment::Static(rest[..end].to_string()));
+            rest = &rest[end..];
+        }
+    }
+
+    // Poem compiles the regular expressions when the routes are built, so check that they will
+    // compile now rather than waiting for Poem to fail.
+    for segment in &segments {
+        if let Segment::Regex(_, regex) = segment {
+            if let Err(err) = regex::Regex::new(regex) {
+                return Err(format!(
+                    "invalid regular expression `{regex}` in `{path}`: {err}"
+                ));
+            }
+        }
+    }
+
+    // Each parameter is extracted by name, so the same name cannot be used twice.
+    for (index, segment) in segments.iter().enumerate() {
+        if let Some(name) = segment.name() {
+            if segments[..index]
+                .iter()
+                .any(|other| other.name() == Some(name))
+            {
+                return Err(format!("duplicate parameter `{segment}` in `{path}`"));
+            }
+        }
+    }
+
+    Ok(segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parse a path and display each of its segments.
+    fn segments(path: &str) -> Vec<String> {
+        parse_segments(path)
+            .unwrap()
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    fn error(path: &str) -> String {
+        match parse_segments(path) {
+            Ok(_) => panic!("expected `{path}` to be rejected"),
+            Err(err) => err,
+        }
+    }
+
+    fn pattern(path: &str) -> String {
+        syn::parse_str::<RoutePath>(&format!("{path:?}"))
+            .unwrap()
+            .pattern()
+    }
+
+    #[test]
+    fn parses_segments() {
+        assert_eq!(segments("/"), ["/"]);
+        assert_eq!(segments("/pastes"), ["/pastes"]);
+        assert_eq!(segments("/pastes/:id"), ["/pastes/", ":id"]);
+        assert_eq!(segments("/pastes/:id/raw"), ["/pastes/", ":id", "/raw"]);
+        assert_eq!(segments("/:id<\\d+>"), ["/", ":id<\\d+>"]);
+        assert_eq!(segments("/<\\d+>/x"), ["/", "<\\d+>", "/x"]);
+        assert_eq!(segments("/files/*path"), ["/files/", "*path"]);
+        assert_eq!(segments("/files/*"), ["/files/", "*"]);
+    }
+
+    #[test]
+    fn parses_names() {
+        let segments = parse_segments("/:a/<x>/:b<y>/*c").unwrap();
+        let names = segments.iter().map(Segment::name).collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                None,
+                Some("a"),
+                None,
+                None,
+                None,
+                Some("b"),
+                None,
+                Some("c")
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_paths() {
+        assert_eq!(error("pastes"), "`pastes` must start with a `/`");
+        assert_eq!(error(""), "`` must start with a `/`");
+        assert_eq!(error("/:"), "missing parameter name after `:` in `/:`");
+        assert_eq!(error("/:/x"), "missing parameter name after `:` in `/:/x`");
+        assert_eq!(
+            error("/:id<\\d+"),
+            "missing `>` after regular expression in `/:id<\\d+`"
+        );
+        assert_eq!(error("/<>"), "empty regular expression in `/<>`");
+        assert_eq!(
+            error("/*path/x"),
+            "wildcard must be at the end of `/*path/x`"
+        );
+        assert_eq!(error("/*/x"), "wildcard must be at the end of `/*/x`");
+    }
+
+    #[test]
+    fn rejects_invalid_regex() {
+        assert!(error("/:id<[>").starts_with("invalid regular expression `[` in `/:id<[>`: "));
+        assert!(error("/<(>").starts_with("invalid regular expression `(` in `/<(>`: "));
+    }
+
+    #[test]
+    fn rejects_duplicate_parameters() {
+        assert_eq!(error("/:id/:id"), "duplicate parameter `:id` in `/:id/:id`");
+        assert_eq!(
+            error("/:id/:id<\\d+>"),
+            "duplicate parameter `:id<\\d+>` in `/:id/:id<\\d+>`"
+        );
+        assert_eq!(
+            error("/:path/*path"),
+            "duplicate parameter `*path` in `/:path/*path`"
+        );
+
+        // Unnamed regular expressions and wildcards do not capture a parameter.
+        assert!(parse_segments("/<a>/<a>").is_ok());
+    }
+
+    #[test]
+    fn reports_error_at_path() {
+        let err = syn::parse_str::<RoutePath>("\"/:id/:id\"").err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "invalid route path: duplicate parameter `:id` in `/:id/:id`"
+        );
+    }
+
+    #[test]
+    fn pattern_ignores_names() {
+        assert_eq!(pattern("/users/:id"), pattern("/users/:name"));
+        assert_eq!(pattern("/files/*path"), pattern("/files/*"));
+        assert_eq!(pattern("/:id<\\d+>"), pattern("/:n<\\d+>"));
+        assert_eq!(pattern("/:id<\\d+>"), pattern("/<\\d+>"));
+        assert_ne!(pattern("/:id<\\d+>"), pattern("/:id"));
+        assert_ne!(pattern("/users/:id"), pattern("/users/*id"));
+        assert_ne!(pattern("/users"), pattern("/users/"));
+    }
+
+    #[test]
+    fn is_static() {
+        let is_static = |path: &str| {
+            syn::parse_str::<RoutePath>(&format!("{path:?}"))
+                .unwrap()
+                .is_static()
+        };
+
+        assert!(is_static("/"));
+        assert!(is_static("/users/admin"));
+        assert!(!is_static("/users/:id"));
+        assert!(!is_static("/<\\d+>"));
+        assert!(!is_static("/files/*"));
+    }
+}
@@ -0,0 +1,184 @@
+//! Generation of constants for the paths of the routes.
+
+use std::collections::HashMap;
+
+use quote::{format_ident, quote};
+use syn::ext::IdentExt;
+
+use crate::{
+    path::{parse_segments, Segment},
+    push_conflict, FlatRoute, Handlers,
+};
+
+/// Convert a path into an upper-case name, made up of its static parts and the names of its
+/// parameters, such as `USERS_ID` for `"/users/:id"`. Unnamed regular expressions and wildcards are
+/// left out of the name.
+fn path_name(path: &str) -> String {
+    parse_segments(path)
+        .unwrap_or_default()
+        .iter()
+        .flat_map(|segment| match segment {
+            Segment::Static(text) => text.split('/').collect(),
+            _ => segment.name().into_iter().collect::<Vec<_>>(),
+        })
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            part.chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() {
+                        c.to_ascii_uppercase()
+                    } else {
+                        '_'
+                    }
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// Get the name of the constant for a route.
+///
+/// Routes that use a handler name template are named after the last segment of the template,
+/// prefixed with the paths of any enclosing nested routes, such as `ADMIN_USERS` for the route
+/// `"/users" users GET` nested under `"/admin"`. A route with explicit handlers has no template, so
+/// it is named after its full path instead, such as `USERS_ID` for `"/users/:id"`, or `ROOT` for
+/// `"/"`.
+fn route_name(route: &FlatRoute) -> syn::Ident {
+    let (name, span) = match &route.route.handlers {
+        Handlers::Template { ident, .. } if !ident.segments.is_empty() => {
+            let last = &ident.segments[ident.segments.len() - 1].ident;
+            let prefix = path_name(&route.prefix);
+            let last_name = last.unraw().to_string().to_uppercase();
+            if prefix.is_empty() {
+                (last_name, last.span())
+            } else {
+                (format!("{prefix}_{last_name}"), last.span())
+            }
+        }
+
+        _ => (path_name(&route.path), route.route.path.span()),
+    };
+
+    if name.is_empty() {
+        syn::Ident::new("ROOT", span)
+    } else if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format_ident!("_{}", name, span = span)
+    } else {
+        syn::Ident::new(&name, span)
+    }
+}
+
+/// Name each of the routes, returning the upper-case name and the first route with that name.
+///
+/// The same name can be given to more than one route, such as when several routes with the same
+/// path are merged, as long as they all have the same path. Otherwise an error is reported, using
+/// `what` to describe the item that is being named.
+pub(crate) fn name_routes<'a, 'b>(
+    routes: &'b [FlatRoute<'a>],
+    what: &str,
+) -> syn::Result<Vec<(syn::Ident, &'b FlatRoute<'a>)>> {
+    let mut error = None;
+    let mut seen: HashMap<String, &FlatRoute> = HashMap::new();
+    let mut named = Vec::new();
+
+    for route in routes {
+        let name = route_name(route);
+        match seen.get(&name.to_string()) {
+            Some(first) if first.path == route.path => {}
+
+            Some(first) => push_conflict(
+                &mut error,
+                (
+                    route.route.path.span(),
+                    format!(
+                        "the {what} `{name}` for route `{}` is already used for route `{}`",
+                        route.path, first.path
+                    ),
+                ),
+                (
+                    first.route.path.span(),
+                    format!("route `{}` is defined here", first.path),
+                ),
+            ),
+
+            None => {
+                seen.insert(name.to_string(), route);
+                named.push((name, route));
+            }
+        }
+    }
+
+    match error {
+        Some(error) => Err(error),
+        None => Ok(named),
+    }
+}
+
+/// Render the module of path constants for the given routes.
+pub(crate) fn render_paths(
+    vis: &syn::Visibility,
+    module: &syn::Ident,
+    routes: &[FlatRoute],
+) -> syn::Result<proc_macro2::TokenStream> {
+    let consts = name_routes(routes, "path constant")?
+        .into_iter()
+        .map(|(name, route)| {
+            let path = &route.path;
+            let doc = format!("The path of the route `{path}`.");
+            quote! {
+                #[doc = #doc]
+                pub const #name: &str = #path;
+            }
+        });
+
+    Ok(quote! {
+        #[allow(dead_code)]
+        #vis mod #module {
+            #(#consts)*
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse::Parser;
+
+    use super::{path_name, route_name};
+    use crate::{flatten_routes, parse_routes};
+
+    /// Name each of the given routes.
+    fn names(routes: &str) -> Vec<String> {
+        let routes = parse_routes.parse_str(routes).unwrap();
+        let mut flat = Vec::new();
+        flatten_routes(&routes, "", "", &mut flat);
+        flat.iter()
+            .map(|route| route_name(route).to_string())
+            .collect()
+    }
+
+    #[test]
+    fn names_routes() {
+        assert_eq!(
+            names(r#""/" index GET "/users/:id" { GET => show } "/m" r#match GET"#),
+            ["INDEX", "USERS_ID", "MATCH"]
+        );
+        assert_eq!(
+            names(r#"* "/admin" routes { "/users" admin::users GET "/" { GET => index } }"#),
+            ["ADMIN_USERS", "ADMIN"]
+        );
+    }
+
+    #[test]
+    fn names_paths() {
+        assert_eq!(path_name("/"), "");
+        assert_eq!(path_name("/users"), "USERS");
+        assert_eq!(path_name("/users/:id"), "USERS_ID");
+        assert_eq!(path_name("/users/:id/posts"), "USERS_ID_POSTS");
+        assert_eq!(path_name("/users/:id<\\d+>"), "USERS_ID");
+        assert_eq!(path_name("/users/<\\d+>"), "USERS");
+        assert_eq!(path_name("/files/*path"), "FILES_PATH");
+        assert_eq!(path_name("/files/*"), "FILES");
+        assert_eq!(path_name("/api/v1.0/user-info"), "API_V1_0_USER_INFO");
+    }
+}
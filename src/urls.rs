@@ -0,0 +1,141 @@
+//! Generation of URL builder functions for the routes.
+
+use quote::{format_ident, quote};
+
+use crate::{
+    path::{parse_segments, Segment},
+    paths::name_routes,
+    FlatRoute,
+};
+
+/// Convert a name into an identifier, appending an underscore if the name is a keyword.
+fn make_ident(name: &str, span: proc_macro2::Span) -> syn::Ident {
+    let name = name.strip_prefix("r#").unwrap_or(name);
+    match syn::parse_str::<syn::Ident>(name) {
+        Ok(_) => syn::Ident::new(name, span),
+        Err(_) => format_ident!("{}_", name, span = span),
+    }
+}
+
+/// Get the name of the argument for a parameter. Parameters without a name, or with a name that is
+/// not a valid identifier, are named after their position in the path.
+fn param_ident(segment: &Segment, index: usize, span: proc_macro2::Span) -> syn::Ident {
+    let name = segment.name().filter(|name| {
+        let mut chars = name.chars();
+        chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+            && *name != "_"
+    });
+
+    match name {
+        Some(name) => make_ident(name, span),
+        None => format_ident!("param{}", index, span = span),
+    }
+}
+
+fn render_url(name: &syn::Ident, route: &FlatRoute) -> syn::Result<proc_macro2::TokenStream> {
+    let span = route.route.path.span();
+    let segments =
+        parse_segments(&route.path).map_err(|err| syn::Error::new(span, err.to_string()))?;
+
+    // The URL is built in a local with mixed-site hygiene, so that it cannot be confused with any of
+    // the arguments, which are named after the parameters.
+    let url = syn::Ident::new("url", proc_macro2::Span::mixed_site());
+    let mut args = Vec::new();
+    let mut body = Vec::new();
+
+    for (index, segment) in segments.iter().enumerate() {
+        match segment {
+            Segment::Static(text) => body.push(quote! {
+                #url.push_str(#text);
+            }),
+
+            Segment::Param(_) | Segment::Regex(_, _) => {
+                let arg = param_ident(segment, index, span);
+                args.push(quote! { #arg: impl ::std::fmt::Display });
+                body.push(quote! {
+                    let _ = encoding::encode(&#arg.to_string(), false, &mut #url);
+                });
+            }
+
+            Segment::CatchAll(_) => {
+                let arg = param_ident(segment, index, span);
+                args.push(quote! { #arg: impl ::std::convert::AsRef<str> });
+                body.push(quote! {
+                    let _ = encoding::encode(#arg.as_ref().trim_start_matches('/'), true, &mut #url);
+                });
+            }
+        }
+    }
+
+    let name = make_ident(&name.to_string().to_lowercase(), name.span());
+    let doc = format!("Build the URL for the route `{}`.", route.path);
+
+    Ok(quote! {
+        #[doc = #doc]
+        pub fn #name(#(#args),*) -> ::std::string::String {
+            let mut #url = ::std::string::String::new();
+            #(#body)*
+            #url
+        }
+    })
+}
+
+/// Render the function that percent-encodes a value into a URL, with the given visibility.
+///
+/// This is also used by `derive(Routes)`, so it only uses the standard library, and writes to
+/// anything that implements `fmt::Write`, such as a `String` or a `Formatter`.
+pub(crate) fn render_encode(vis: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    quote! {
+        /// Percent-encode a value into the URL, leaving only the unreserved characters. When
+        /// `keep_slash` is set, any slashes are kept, so that a path can be given for a wildcard.
+        #vis fn encode(
+            value: &str,
+            keep_slash: bool,
+            out: &mut impl ::std::fmt::Write,
+        ) -> ::std::fmt::Result {
+            for byte in value.bytes() {
+                match byte {
+                    b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                        out.write_char(byte as char)?
+                    }
+
+                    b'/' if keep_slash => out.write_char('/')?,
+                    _ => ::std::write!(out, "%{byte:02X}")?,
+                }
+            }
+
+            Ok(())
+        }
+    }
+}
+
+/// Render the module of URL builder functions for the given routes.
+///
+/// The encoding function is kept in a submodule, which is in the type namespace, so that it does
+/// not conflict with a URL builder of the same name.
+pub(crate) fn render_urls(
+    vis: &syn::Visibility,
+    module: &syn::Ident,
+    routes: &[FlatRoute],
+) -> syn::Result<proc_macro2::TokenStream> {
+    let functions = name_routes(routes, "URL builder")?
+        .into_iter()
+        .map(|(name, route)| render_url(&name, route))
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let encode = render_encode(quote! { pub(super) });
+
+    Ok(quote! {
+        #[allow(dead_code)]
+        #vis mod #module {
+            #(#functions)*
+
+            mod encoding {
+                #encode
+            }
+        }
+    })
+}
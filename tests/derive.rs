@@ -0,0 +1,128 @@
+use poem::{handler, http::StatusCode, test::TestClient};
+use poem_route_macro::Routes;
+
+#[derive(Routes, Debug, PartialEq)]
+enum AppRoute {
+    #[at("/", GET)]
+    Index,
+    #[at("/pastes/:id", GET POST)]
+    Paste { id: u64 },
+    #[at("/files/*path")]
+    File(String),
+}
+
+#[handler]
+fn get_index() -> &'static str {
+    "index"
+}
+
+#[handler]
+fn get_paste() -> &'static str {
+    "get paste"
+}
+
+#[handler]
+fn post_paste() -> &'static str {
+    "post paste"
+}
+
+/// An enum without any methods, which does not have a `router` function.
+#[derive(Routes, Debug, PartialEq)]
+enum SharedRoute {
+    #[at("/users/:name")]
+    User { name: String },
+}
+
+/// An enum whose fields have the same names as the locals of the generated code.
+#[derive(Routes, Debug, PartialEq)]
+enum ShadowRoute {
+    #[at("/a/:rest/:value/:f")]
+    A { rest: String, value: u32, f: u32 },
+    #[at("/b/:path/:url/:matched/*route")]
+    B {
+        path: String,
+        url: String,
+        matched: u32,
+        route: String,
+    },
+    #[at("/c/:encode/:decode")]
+    C { encode: String, decode: String },
+}
+
+#[test]
+fn displays_routes() {
+    assert_eq!(AppRoute::Index.to_string(), "/");
+    assert_eq!(AppRoute::Paste { id: 42 }.to_string(), "/pastes/42");
+    assert_eq!(
+        AppRoute::File("/docs/read me.txt".to_string()).to_string(),
+        "/files/docs/read%20me.txt"
+    );
+    assert_eq!(
+        SharedRoute::User {
+            name: "a/b".to_string()
+        }
+        .to_string(),
+        "/users/a%2Fb"
+    );
+}
+
+#[test]
+fn parses_routes() {
+    assert_eq!("/".parse(), Ok(AppRoute::Index));
+    assert_eq!("/pastes/42?raw=1".parse(), Ok(AppRoute::Paste { id: 42 }));
+    assert_eq!(
+        "/files/docs/read%20me.txt".parse(),
+        Ok(AppRoute::File("docs/read me.txt".to_string()))
+    );
+    assert_eq!(
+        "/users/a%2Fb".parse(),
+        Ok(SharedRoute::User {
+            name: "a/b".to_string()
+        })
+    );
+
+    assert!("/pastes/abc".parse::<AppRoute>().is_err());
+    assert!("/pastes/".parse::<AppRoute>().is_err());
+    assert!("/pastes/42/raw".parse::<AppRoute>().is_err());
+}
+
+#[tokio::test]
+async fn routes_methods() {
+    let client = TestClient::new(AppRoute::router());
+    let resp = client.get("/").send().await;
+    resp.assert_text("index").await;
+
+    let resp = client.post("/pastes/42").send().await;
+    resp.assert_text("post paste").await;
+
+    let resp = client.get("/files/x").send().await;
+    resp.assert_status(StatusCode::NOT_FOUND);
+}
+
+#[test]
+fn fields_do_not_shadow_locals() {
+    let routes = [
+        ShadowRoute::A {
+            rest: "x".to_string(),
+            value: 1,
+            f: 2,
+        },
+        ShadowRoute::B {
+            path: "p".to_string(),
+            url: "u".to_string(),
+            matched: 3,
+            route: "r/s".to_string(),
+        },
+        ShadowRoute::C {
+            encode: "e".to_string(),
+            decode: "d".to_string(),
+        },
+    ];
+
+    let urls = routes.iter().map(ToString::to_string).collect::<Vec<_>>();
+    assert_eq!(urls, ["/a/x/1/2", "/b/p/u/3/r/s", "/c/e/d"]);
+
+    for (route, url) in routes.into_iter().zip(urls) {
+        assert_eq!(url.parse(), Ok(route));
+    }
+}
@@ -0,0 +1,72 @@
+use poem::{handler, test::TestClient, Route};
+use poem_route_macro::define_routes;
+
+#[handler]
+fn get_index() -> &'static str {
+    "index"
+}
+
+#[handler]
+fn get_paste() -> &'static str {
+    "paste"
+}
+
+define_routes!(
+    /// The routes, defined in a plain function.
+    #[allow(dead_code)]
+    pub fn plain() -> Route {
+        "/" index GET
+    }
+);
+
+define_routes!(pub(crate) async fn asynchronous() -> Route {
+    "/" index GET
+});
+
+define_routes!(
+    /// # Safety
+    ///
+    /// This is only unsafe to check that the qualifier is accepted.
+    unsafe fn qualified() -> Route {
+        "/" index GET
+    }
+);
+
+define_routes!(paths = paths, fn with_paths() -> Route {
+    "/pastes/:id" paste GET
+});
+
+define_routes!(manifest = manifest, fn with_manifest() -> Route {
+    "/"             index   GET
+    "/pastes/:id"   paste   GET
+});
+
+#[tokio::test]
+async fn defines_functions() {
+    let resp = TestClient::new(plain()).get("/").send().await;
+    resp.assert_text("index").await;
+
+    let resp = TestClient::new(asynchronous().await).get("/").send().await;
+    resp.assert_text("index").await;
+
+    let resp = TestClient::new(unsafe { qualified() })
+        .get("/")
+        .send()
+        .await;
+    resp.assert_text("index").await;
+
+    let resp = TestClient::new(with_paths()).get("/pastes/1").send().await;
+    resp.assert_text("paste").await;
+    assert_eq!(paths::PASTE, "/pastes/:id");
+}
+
+#[test]
+fn describes_routes() {
+    let _ = with_manifest();
+    let routes = manifest::ROUTES;
+    assert_eq!(routes.len(), 2);
+    assert_eq!(routes[1].path, "/pastes/:id");
+    assert_eq!(routes[1].methods, ["GET"]);
+    assert_eq!(routes[1].handlers, ["get_paste"]);
+    assert_eq!(routes[1].line, routes[0].line + 1);
+}
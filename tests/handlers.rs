@@ -0,0 +1,64 @@
+use poem::{http::Method, http::StatusCode, test::TestClient, web::Path};
+use poem_route_macro::{collect_routes, get, post, route};
+
+#[get("/pastes/:id")]
+fn get_paste(Path(id): Path<u64>) -> String {
+    format!("get paste {id}")
+}
+
+#[post("/pastes/:id")]
+fn post_paste(Path(id): Path<u64>) -> String {
+    format!("post paste {id}")
+}
+
+#[route("/pastes/:id", PROPFIND)]
+fn propfind_paste(Path(id): Path<u64>) -> String {
+    format!("propfind paste {id}")
+}
+
+#[get("/")]
+fn index() -> &'static str {
+    "index"
+}
+
+#[get("/pastes/:id")]
+fn get_paste_again() -> &'static str {
+    "get paste again"
+}
+
+#[tokio::test]
+async fn merges_methods() {
+    let cli = TestClient::new(collect_routes![
+        index,
+        get_paste,
+        post_paste,
+        propfind_paste
+    ]);
+
+    cli.get("/").send().await.assert_text("index").await;
+    cli.get("/pastes/1")
+        .send()
+        .await
+        .assert_text("get paste 1")
+        .await;
+    cli.post("/pastes/2")
+        .send()
+        .await
+        .assert_text("post paste 2")
+        .await;
+    cli.request(Method::from_bytes(b"PROPFIND").unwrap(), "/pastes/3")
+        .send()
+        .await
+        .assert_text("propfind paste 3")
+        .await;
+    cli.put("/pastes/4")
+        .send()
+        .await
+        .assert_status(StatusCode::METHOD_NOT_ALLOWED);
+}
+
+#[test]
+#[should_panic(expected = "duplicate method `GET` for route `/pastes/:id`")]
+fn rejects_duplicate_methods() {
+    let _ = collect_routes![get_paste, get_paste_again];
+}
@@ -0,0 +1,302 @@
+use poem::{
+    error::NotFoundError, handler, http::Method, http::StatusCode, middleware::SetHeader,
+    test::TestClient, IntoEndpoint, Request, Route,
+};
+use poem_route_macro::define_routes;
+
+#[handler]
+fn get_collection() -> &'static str {
+    "get collection"
+}
+
+#[handler]
+fn propfind_collection() -> &'static str {
+    "propfind collection"
+}
+
+#[handler]
+fn mkcol_collection() -> &'static str {
+    "mkcol collection"
+}
+
+#[handler]
+fn propfind_props() -> &'static str {
+    "propfind props"
+}
+
+#[handler]
+fn forward(req: &Request) -> String {
+    format!("{} {}", req.method(), req.uri().path())
+}
+
+mod users {
+    use poem::handler;
+
+    #[handler]
+    pub fn list() -> &'static str {
+        "list users"
+    }
+
+    #[handler]
+    pub fn create() -> &'static str {
+        "create user"
+    }
+
+    #[handler]
+    pub fn get_user() -> &'static str {
+        "get user"
+    }
+}
+
+mod naming {
+    use poem::handler;
+
+    #[handler]
+    pub fn paste_get() -> &'static str {
+        "suffix"
+    }
+
+    pub mod get {
+        use poem::handler;
+
+        #[handler]
+        pub fn paste() -> &'static str {
+            "module"
+        }
+    }
+}
+
+#[handler]
+fn legacy(req: &Request) -> String {
+    req.uri().path().to_string()
+}
+
+/// Data added to all of the routes.
+#[derive(Clone)]
+struct Site(&'static str);
+
+/// Data added to a group of routes.
+#[derive(Clone)]
+struct Section(&'static str);
+
+/// Data added to a single route.
+#[derive(Clone)]
+struct Page(&'static str);
+
+mod admin {
+    use poem::{handler, web::Data};
+
+    use super::{Page, Section, Site};
+
+    #[handler]
+    pub fn get_users(
+        Data(Site(site)): Data<&Site>,
+        Data(Section(section)): Data<&Section>,
+        Data(Page(page)): Data<&Page>,
+    ) -> String {
+        format!("{site} {section} {page}")
+    }
+
+    #[handler]
+    pub fn get_log(Data(Section(section)): Data<&Section>) -> String {
+        format!("{section} log")
+    }
+}
+
+#[handler]
+fn get_post() -> &'static str {
+    "get post"
+}
+
+#[handler]
+fn post_post() -> &'static str {
+    "post post"
+}
+
+async fn admin_not_found(_: NotFoundError) -> &'static str {
+    "admin not found"
+}
+
+async fn error_page(err: poem::Error) -> (StatusCode, String) {
+    (err.status(), format!("fallback: {err}"))
+}
+
+fn methods() -> Route {
+    define_routes!({
+        "/dav"      collection  GET PROPFIND MKCOL
+        "/props"    props       PROPFIND
+        "/proxy"    forward     ANY
+        "/users"    { GET => users::list, POST => users::create }
+        "/users/:id" users::user GET
+    })
+}
+
+fn suffix() -> Route {
+    define_routes!(naming = suffix, {
+        "/paste" naming::paste GET
+    })
+}
+
+fn module() -> Route {
+    define_routes!(naming = module, {
+        "/paste" naming::paste GET
+    })
+}
+
+fn nested() -> Route {
+    define_routes!({
+        **"/legacy" { poem::Route::new().at("/legacy/page", legacy) }
+        *"/stripped" { poem::Route::new().at("/page", legacy) }
+    })
+}
+
+fn groups() -> impl IntoEndpoint {
+    define_routes!({
+        group "/admin" data(Section("admin")) with SetHeader::new().appending("x-group", "admin") {
+            "/users"        admin::users    GET data(Page("users"))
+            group "/audit" with SetHeader::new().appending("x-group", "audit") {
+                "/log"          admin::log      GET
+            }
+        }
+        "/posts"        post    GET POST(with SetHeader::new().appending("x-csrf", "checked"))
+    } data(Site("example")))
+}
+
+fn errors() -> impl IntoEndpoint {
+    define_routes!({
+        "/posts"        post    GET
+        group "/admin" catch NotFoundError => admin_not_found {
+            "/dav"          collection      GET
+        }
+        *"/docs" { Route::new().at("/intro", legacy) } catch NotFoundError => |_| async {
+            "docs not found"
+        }
+    } fallback => error_page)
+}
+
+#[tokio::test]
+async fn routes_extension_methods() {
+    let client = TestClient::new(methods());
+    let propfind = Method::from_bytes(b"PROPFIND").unwrap();
+
+    let resp = client.get("/dav").send().await;
+    resp.assert_text("get collection").await;
+
+    let resp = client.request(propfind.clone(), "/dav").send().await;
+    resp.assert_text("propfind collection").await;
+
+    let resp = client
+        .request(Method::from_bytes(b"MKCOL").unwrap(), "/dav")
+        .send()
+        .await;
+    resp.assert_text("mkcol collection").await;
+
+    // An extension method can also be the first method of a route.
+    let resp = client.request(propfind, "/props").send().await;
+    resp.assert_text("propfind props").await;
+
+    let resp = client.get("/props").send().await;
+    resp.assert_status(StatusCode::METHOD_NOT_ALLOWED);
+}
+
+#[tokio::test]
+async fn routes_any_method() {
+    let client = TestClient::new(methods());
+    let resp = client.delete("/proxy").send().await;
+    resp.assert_text("DELETE /proxy").await;
+
+    let resp = client
+        .request(Method::from_bytes(b"PROPFIND").unwrap(), "/proxy")
+        .send()
+        .await;
+    resp.assert_text("PROPFIND /proxy").await;
+}
+
+#[tokio::test]
+async fn routes_explicit_handlers() {
+    let client = TestClient::new(methods());
+    let resp = client.get("/users").send().await;
+    resp.assert_text("list users").await;
+
+    let resp = client.post("/users").send().await;
+    resp.assert_text("create user").await;
+
+    let resp = client.get("/users/1").send().await;
+    resp.assert_text("get user").await;
+}
+
+#[tokio::test]
+async fn names_handlers() {
+    let resp = TestClient::new(suffix()).get("/paste").send().await;
+    resp.assert_text("suffix").await;
+
+    let resp = TestClient::new(module()).get("/paste").send().await;
+    resp.assert_text("module").await;
+}
+
+#[tokio::test]
+async fn nests_without_stripping() {
+    let client = TestClient::new(nested());
+    let resp = client.get("/legacy/page").send().await;
+    resp.assert_text("/legacy/page").await;
+
+    let resp = client.get("/stripped/page").send().await;
+    resp.assert_text("/page").await;
+}
+
+#[tokio::test]
+async fn adds_data_to_groups() {
+    let client = TestClient::new(groups());
+    let resp = client.get("/admin/users").send().await;
+    resp.assert_header("x-group", "admin");
+    resp.assert_text("example admin users").await;
+
+    let resp = client.get("/admin/audit/log").send().await;
+    resp.assert_header_all("x-group", ["admin", "audit"]);
+    resp.assert_text("admin log").await;
+}
+
+#[tokio::test]
+async fn wraps_single_methods() {
+    let client = TestClient::new(groups());
+    let resp = client.get("/posts").send().await;
+    resp.assert_header_is_not_exist("x-csrf");
+    resp.assert_header_is_not_exist("x-group");
+    resp.assert_text("get post").await;
+
+    let resp = client.post("/posts").send().await;
+    resp.assert_header("x-csrf", "checked");
+    resp.assert_text("post post").await;
+}
+
+#[tokio::test]
+async fn catches_errors_in_groups() {
+    let client = TestClient::new(errors());
+    let resp = client.get("/admin/dav").send().await;
+    resp.assert_text("get collection").await;
+
+    let resp = client.get("/admin/missing").send().await;
+    resp.assert_status_is_ok();
+    resp.assert_text("admin not found").await;
+
+    let resp = client.get("/docs/intro").send().await;
+    resp.assert_text("/intro").await;
+
+    let resp = client.get("/docs/missing").send().await;
+    resp.assert_text("docs not found").await;
+}
+
+#[tokio::test]
+async fn falls_back_for_unmatched_paths() {
+    let client = TestClient::new(errors());
+    let resp = client.get("/posts").send().await;
+    resp.assert_text("get post").await;
+
+    let resp = client.get("/missing").send().await;
+    resp.assert_status(StatusCode::NOT_FOUND);
+    resp.assert_text("fallback: not found").await;
+
+    let resp = client.post("/posts").send().await;
+    resp.assert_status(StatusCode::METHOD_NOT_ALLOWED);
+    resp.assert_text("fallback: method not allowed").await;
+}
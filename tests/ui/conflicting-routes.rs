@@ -0,0 +1,10 @@
+use poem_route_macro::define_routes;
+
+define_routes!(fn routes() -> poem::Route {
+    "/users/:id" user GET
+    "/users/:name" named POST
+    "/api/*rest" rest GET
+    * "/api" { api() }
+});
+
+fn main() {}
@@ -0,0 +1,8 @@
+use poem_route_macro::define_routes;
+
+define_routes!(fn routes() -> poem::Route {
+    "/users" users GET
+    "/users" list GET
+});
+
+fn main() {}
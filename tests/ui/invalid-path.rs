@@ -0,0 +1,8 @@
+use poem_route_macro::define_routes;
+
+define_routes!(fn routes() -> poem::Route {
+    "/" index GET
+    "/pastes/:id/:id" paste GET
+});
+
+fn main() {}
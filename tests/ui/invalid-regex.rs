@@ -0,0 +1,7 @@
+use poem_route_macro::define_routes;
+
+define_routes!(fn routes() -> poem::Route {
+    "/pastes/:id<[0-9>" paste GET
+});
+
+fn main() {}
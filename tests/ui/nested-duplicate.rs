@@ -0,0 +1,10 @@
+use poem_route_macro::define_routes;
+
+define_routes!(fn routes() -> poem::Route {
+    * "/admin" routes {
+        "/users" users GET
+        "/users" list GET
+    }
+});
+
+fn main() {}
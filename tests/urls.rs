@@ -0,0 +1,48 @@
+use poem::{handler, Route};
+use poem_route_macro::define_routes;
+
+#[handler]
+fn get_paste() {}
+
+#[handler]
+fn get_file() {}
+
+#[handler]
+fn get_embed() {}
+
+#[handler]
+fn get_encode() {}
+
+#[handler]
+fn get_match() {}
+
+define_routes!(paths = paths, urls = urls, fn routes() -> Route {
+    "/pastes/:id"           paste   GET
+    "/files/:user/*path"    file    GET
+    "/embed/:url"           embed   GET
+    "/encode/:encoding"     encode  GET
+    "/match"                r#match GET
+});
+
+#[test]
+fn builds_urls() {
+    let _ = routes();
+    assert_eq!(urls::paste(42), "/pastes/42");
+    assert_eq!(urls::paste("a/b c"), "/pastes/a%2Fb%20c");
+    assert_eq!(
+        urls::file("bob", "/docs/read me.txt"),
+        "/files/bob/docs/read%20me.txt"
+    );
+}
+
+#[test]
+fn parameters_do_not_shadow_locals() {
+    assert_eq!(urls::embed(42), "/embed/42");
+    assert_eq!(urls::encode("x"), "/encode/x");
+}
+
+#[test]
+fn names_raw_identifiers() {
+    assert_eq!(paths::MATCH, "/match");
+    assert_eq!(urls::match_(), "/match");
+}